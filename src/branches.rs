@@ -0,0 +1,115 @@
+use crate::circle_coord::CircleCoord;
+use crate::maze::Maze;
+use std::collections::{HashSet, VecDeque};
+
+/// A dead-end branch leaving the solution path at `root`. Every cell of the branch is listed
+/// with its number of steps from the path.
+#[derive(Debug)]
+pub struct Branch {
+    root: CircleCoord,
+    cells: Vec<(CircleCoord, usize)>,
+}
+
+impl Branch {
+    pub fn root(&self) -> &CircleCoord {
+        &self.root
+    }
+
+    pub fn cells(&self) -> &[(CircleCoord, usize)] {
+        &self.cells
+    }
+
+    pub fn depth(&self) -> usize {
+        self.cells.iter().map(|(_, depth)| *depth).max().unwrap_or(0)
+    }
+}
+
+/// Splits the cells off the solution path into the branches hanging off it, in path order.
+pub fn find_branches(maze: &Maze, path: &[CircleCoord]) -> Vec<Branch> {
+    let mut visited: HashSet<CircleCoord> = path.iter().cloned().collect();
+    let mut branches = Vec::new();
+
+    for root in path {
+        for entry in maze.accessible_neighbours(root) {
+            if !visited.insert(entry.clone()) {
+                continue;
+            }
+
+            let mut cells = Vec::new();
+            let mut queue = VecDeque::from([(entry, 1)]);
+            while let Some((current, depth)) = queue.pop_front() {
+                for neighbour in maze.accessible_neighbours(&current) {
+                    if visited.insert(neighbour.clone()) {
+                        queue.push_back((neighbour, depth + 1));
+                    }
+                }
+                cells.push((current, depth));
+            }
+
+            branches.push(Branch { root: root.clone(), cells });
+        }
+    }
+
+    branches
+}
+
+/// The cells of the solution path where a branch leaves it, so the player has to choose.
+pub fn decision_points(branches: &[Branch]) -> Vec<CircleCoord> {
+    let mut points: Vec<CircleCoord> = Vec::new();
+    for branch in branches {
+        if points.last() != Some(branch.root()) {
+            points.push(branch.root().clone());
+        }
+    }
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circle_coord::RingLayout;
+    use crate::maze::factory;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_branches_cover_every_cell_off_the_path() {
+        let layout = RingLayout::default();
+        let mut rng = StdRng::seed_from_u64(42);
+        let maze = factory(6, &layout, &mut rng);
+        let path = maze.tree_diameter();
+        let branches = find_branches(&maze, &path);
+
+        let cells: usize = (0..6).map(|c| layout.total_arcs(c)).sum();
+        let branch_cells: usize = branches.iter().map(|b| b.cells().len()).sum();
+        assert_eq!(branch_cells + path.len(), cells);
+
+        for branch in &branches {
+            assert!(path.contains(branch.root()));
+            let (entry, depth) = &branch.cells()[0];
+            assert_eq!(*depth, 1);
+            assert!(maze.accessible_neighbours(branch.root()).contains(entry));
+        }
+    }
+
+    #[test]
+    fn test_decision_points_follow_the_path() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let maze = factory(6, &RingLayout::default(), &mut rng);
+        let path = maze.tree_diameter();
+        let branches = find_branches(&maze, &path);
+        let points = decision_points(&branches);
+
+        let positions: Vec<usize> = points
+            .iter()
+            .map(|point| path.iter().position(|c| c == point).unwrap())
+            .collect();
+        assert!(positions.windows(2).all(|w| w[0] < w[1]));
+
+        for (i, cell) in path.iter().enumerate() {
+            let exits = maze.accessible_neighbours(cell).len();
+            let on_path = if i == 0 || i == path.len() - 1 { 1 } else { 2 };
+            assert_eq!(points.contains(cell), exits > on_path);
+        }
+    }
+}
@@ -1,4 +1,7 @@
+use crate::error::MazeError;
 use fraction::Fraction;
+use std::f64::consts::PI;
+use std::cmp::Ordering;
 use std::hash::{Hash, Hasher};
 
 const ANGLE_FULL_CIRCLE: usize = 360;
@@ -8,13 +11,118 @@ const ANGLE_FULL_CIRCLE: usize = 360;
 // (The angle for which the length of the arc equals the radius is 1 rad ~ 57.3 degrees.)
 const ANGULAR_DIVISOR_FIRST_CIRCLE: usize = 60;
 
-const ARCS_FIRST_CIRCLE: usize = ANGLE_FULL_CIRCLE / ANGULAR_DIVISOR_FIRST_CIRCLE;
+pub const DEFAULT_FIRST_RING_ARCS: usize = ANGLE_FULL_CIRCLE / ANGULAR_DIVISOR_FIRST_CIRCLE;
+
+const MIN_FIRST_RING_ARCS: usize = 2;
+
+// The most arcs a ring can have, so that the angle of every arc is a fraction of the full
+// circle whose numerator still fits.
+const MAX_RING_ARCS: usize = usize::MAX / ANGLE_FULL_CIRCLE;
+const MIN_WIDTH_RATIO: f64 = 1.0;
+
+/// Decides at which circles the number of arcs doubles.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Subdivision {
+    /// Double the arcs at every circle that is a power of two.
+    PowerOfTwo,
+    /// Double the arcs as soon as the width of a cell, measured at its middle, exceeds the
+    /// given ratio of its depth. This keeps cells close to square.
+    WidthRatio(f64),
+}
+
+/// Describes how the rings of a maze are divided into arcs.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RingLayout {
+    first_ring_arcs: usize,
+    subdivision: Subdivision,
+}
+
+impl Default for RingLayout {
+    fn default() -> Self {
+        RingLayout {
+            first_ring_arcs: DEFAULT_FIRST_RING_ARCS,
+            subdivision: Subdivision::PowerOfTwo,
+        }
+    }
+}
+
+impl RingLayout {
+    pub fn new(first_ring_arcs: usize, subdivision: Subdivision) -> Result<Self, MazeError> {
+        if first_ring_arcs < MIN_FIRST_RING_ARCS {
+            return Err(MazeError::options(format!(
+                "first ring must have at least {} arcs",
+                MIN_FIRST_RING_ARCS
+            )));
+        }
+
+        if let Subdivision::WidthRatio(ratio) = subdivision {
+            if !ratio.is_finite() || ratio < MIN_WIDTH_RATIO {
+                return Err(MazeError::options(format!(
+                    "width ratio must be at least {}",
+                    MIN_WIDTH_RATIO
+                )));
+            }
+        }
+
+        Ok(RingLayout {
+            first_ring_arcs,
+            subdivision,
+        })
+    }
+
+    pub fn first_ring_arcs(&self) -> usize {
+        self.first_ring_arcs
+    }
+
+    pub fn subdivision(&self) -> Subdivision {
+        self.subdivision
+    }
+
+    pub fn total_arcs(&self, circle: usize) -> usize {
+        self.checked_total_arcs(circle).expect("The ring has too many arcs to count")
+    }
+
+    /// The number of arcs of the ring, or `None` if it has more than can be counted.
+    pub fn checked_total_arcs(&self, circle: usize) -> Option<usize> {
+        if circle == 0 {
+            return Some(1);
+        }
+
+        let arcs = match self.subdivision {
+            Subdivision::PowerOfTwo => {
+                round_down_to_power_of2(circle).checked_mul(self.first_ring_arcs)?
+            }
+            Subdivision::WidthRatio(ratio) => {
+                // Jumps from one doubling to the next instead of visiting every ring, since the
+                // width of a cell only grows outwards until its ring is split.
+                let mut arcs = self.first_ring_arcs;
+                let mut from = 2;
+                while from <= circle {
+                    match first_wide_circle(from, circle, arcs, ratio) {
+                        Some(wide) => {
+                            arcs = arcs.checked_mul(2)?;
+                            from = wide + 1;
+                        }
+                        None => break,
+                    }
+                }
+                arcs
+            }
+        };
+        (arcs <= MAX_RING_ARCS).then_some(arcs)
+    }
+
+    fn angle_step(&self, circle: usize) -> Fraction {
+        Fraction::from(ANGLE_FULL_CIRCLE) / Fraction::from(self.total_arcs(circle))
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct CircleCoord {
     circle: usize,
     arc_index: usize,
     angle: Fraction,
+    layout: RingLayout,
 }
 
 impl PartialEq for CircleCoord {
@@ -25,6 +133,20 @@ impl PartialEq for CircleCoord {
 
 impl Eq for CircleCoord {}
 
+// Ring by ring from the centre, then clockwise. Everything written out of a maze is sorted this
+// way so that the same maze always gives the same output.
+impl Ord for CircleCoord {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.circle, self.arc_index).cmp(&(other.circle, other.arc_index))
+    }
+}
+
+impl PartialOrd for CircleCoord {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 impl Hash for CircleCoord {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.circle.hash(state);
@@ -33,25 +155,42 @@ impl Hash for CircleCoord {
 }
 
 impl CircleCoord {
-    pub fn new(circle: usize, arc_index: usize, angle: Fraction) -> Self {
+    pub fn new(circle: usize, arc_index: usize, angle: Fraction, layout: &RingLayout) -> Self {
         CircleCoord {
             circle,
             arc_index,
             angle,
+            layout: *layout,
         }
     }
 
-    pub fn create_with_arc_index(circle: usize, arc_index: usize) -> Self {
-        let angle = calc_angle_step(circle) * Fraction::from(arc_index);
-        Self::new(circle, arc_index, angle)
+    pub fn create_with_arc_index(circle: usize, arc_index: usize, layout: &RingLayout) -> Self {
+        Self::checked_with_arc_index(circle, arc_index, layout)
+            .expect("The arc lies outside its ring")
     }
 
-    pub fn create_with_fraction(circle: usize, angle: Fraction) -> Self {
-        let step = calc_angle_step(circle);
+    /// The coordinate of an arc, or `None` if its ring does not have that many arcs.
+    pub fn checked_with_arc_index(
+        circle: usize,
+        arc_index: usize,
+        layout: &RingLayout,
+    ) -> Option<Self> {
+        let total_arcs = layout.checked_total_arcs(circle)?;
+        if arc_index >= total_arcs {
+            return None;
+        }
+        // Below MAX_RING_ARCS, so the numerator cannot overflow.
+        let degrees = (ANGLE_FULL_CIRCLE * arc_index) as u64;
+        let angle = Fraction::new(degrees, total_arcs as u64);
+        Some(Self::new(circle, arc_index, angle, layout))
+    }
+
+    pub fn create_with_fraction(circle: usize, angle: Fraction, layout: &RingLayout) -> Self {
+        let step = layout.angle_step(circle);
         let arc_index_fraction = angle / step;
         let arc_index =
             (*arc_index_fraction.numer().unwrap() / *arc_index_fraction.denom().unwrap()) as usize;
-        Self::new(circle, arc_index, angle)
+        Self::new(circle, arc_index, angle, layout)
     }
 
     pub fn angle(&self) -> &Fraction {
@@ -66,44 +205,41 @@ impl CircleCoord {
         self.arc_index
     }
 
+    pub fn layout(&self) -> &RingLayout {
+        &self.layout
+    }
+
     pub fn next_clockwise(&self) -> Self {
-        let next_arc_index = (self.arc_index + 1) % calc_total_arcs(self.circle);
-        Self::create_with_arc_index(self.circle, next_arc_index)
+        let next_arc_index = (self.arc_index + 1) % self.layout.total_arcs(self.circle);
+        Self::create_with_arc_index(self.circle, next_arc_index, &self.layout)
     }
 
     pub fn next_out(&self) -> Self {
-        Self::create_with_fraction(self.circle + 1, self.angle)
+        Self::create_with_fraction(self.circle + 1, self.angle, &self.layout)
     }
 
     pub fn next_counter_clockwise(&self) -> Self {
-        let total_arcs = calc_total_arcs(self.circle);
+        let total_arcs = self.layout.total_arcs(self.circle);
         let next_arc_index = (self.arc_index + total_arcs - 1) % total_arcs;
-        Self::create_with_arc_index(self.circle, next_arc_index)
+        Self::create_with_arc_index(self.circle, next_arc_index, &self.layout)
     }
 
     pub fn next_in(&self) -> Self {
         if self.circle == 1 {
-            return CircleCoord::create_with_arc_index(0, 0);
+            return CircleCoord::create_with_arc_index(0, 0, &self.layout);
         }
 
         let result_arc_index: usize =
-            if calc_total_arcs(self.circle) == calc_total_arcs(self.circle - 1) {
+            if self.layout.total_arcs(self.circle) == self.layout.total_arcs(self.circle - 1) {
                 self.arc_index
             } else {
                 self.arc_index / 2
             };
 
-        Self::create_with_arc_index(self.circle - 1, result_arc_index)
+        Self::create_with_arc_index(self.circle - 1, result_arc_index, &self.layout)
     }
 }
 
-pub fn calc_total_arcs(circle: usize) -> usize {
-    if circle == 0 {
-        return 1;
-    }
-    round_down_to_power_of2(circle) * ARCS_FIRST_CIRCLE
-}
-
 fn round_down_to_power_of2(n: usize) -> usize {
     if n == 0 {
         return 1;
@@ -112,8 +248,28 @@ fn round_down_to_power_of2(n: usize) -> usize {
     1 << msb_pos
 }
 
-fn calc_angle_step(circle: usize) -> Fraction {
-    Fraction::from(ANGLE_FULL_CIRCLE) / Fraction::from(calc_total_arcs(circle))
+// The width of a cell on the given circle, measured at the middle of the ring and expressed
+// in ring depths.
+fn calc_cell_width(circle: usize, arcs: usize) -> f64 {
+    2.0 * PI * (circle as f64 + 0.5) / arcs as f64
+}
+
+// The first circle from `from` to `to` whose cells would be wider than `ratio` with `arcs` arcs,
+// found by bisection since the width grows with the circle.
+fn first_wide_circle(from: usize, to: usize, arcs: usize, ratio: f64) -> Option<usize> {
+    if calc_cell_width(to, arcs) <= ratio {
+        return None;
+    }
+    let (mut low, mut high) = (from, to);
+    while low < high {
+        let middle = low + (high - low) / 2;
+        if calc_cell_width(middle, arcs) > ratio {
+            high = middle;
+        } else {
+            low = middle + 1;
+        }
+    }
+    Some(low)
 }
 
 #[cfg(test)]
@@ -134,74 +290,146 @@ mod tests {
     }
 
     #[test]
-    fn test_calc_total_arcs() {
-        assert_eq!(calc_total_arcs(0), 1);
-        assert_eq!(calc_total_arcs(1), 6);
-        assert_eq!(calc_total_arcs(2), 12);
-        assert_eq!(calc_total_arcs(3), 12);
-        assert_eq!(calc_total_arcs(4), 24);
-        assert_eq!(calc_total_arcs(5), 24);
-        assert_eq!(calc_total_arcs(6), 24);
-        assert_eq!(calc_total_arcs(7), 24);
-        assert_eq!(calc_total_arcs(8), 48);
-        assert_eq!(calc_total_arcs(9), 48);
-        assert_eq!(calc_total_arcs(10), 48);
+    fn test_total_arcs() {
+        let layout = RingLayout::default();
+        assert_eq!(layout.total_arcs(0), 1);
+        assert_eq!(layout.total_arcs(1), 6);
+        assert_eq!(layout.total_arcs(2), 12);
+        assert_eq!(layout.total_arcs(3), 12);
+        assert_eq!(layout.total_arcs(4), 24);
+        assert_eq!(layout.total_arcs(5), 24);
+        assert_eq!(layout.total_arcs(6), 24);
+        assert_eq!(layout.total_arcs(7), 24);
+        assert_eq!(layout.total_arcs(8), 48);
+        assert_eq!(layout.total_arcs(9), 48);
+        assert_eq!(layout.total_arcs(10), 48);
+    }
+
+    #[test]
+    fn test_total_arcs_with_first_ring_arcs() {
+        let layout = RingLayout::new(8, Subdivision::PowerOfTwo).unwrap();
+        assert_eq!(layout.total_arcs(0), 1);
+        assert_eq!(layout.total_arcs(1), 8);
+        assert_eq!(layout.total_arcs(3), 16);
+        assert_eq!(layout.total_arcs(4), 32);
+    }
+
+    #[test]
+    fn test_total_arcs_with_width_ratio() {
+        let layout = RingLayout::new(6, Subdivision::WidthRatio(1.5)).unwrap();
+        assert_eq!(layout.total_arcs(0), 1);
+        assert_eq!(layout.total_arcs(1), 6);
+        assert_eq!(layout.total_arcs(2), 12);
+        assert_eq!(layout.total_arcs(3), 24);
+        assert_eq!(layout.total_arcs(4), 24);
+        assert_eq!(layout.total_arcs(5), 24);
+        assert_eq!(layout.total_arcs(6), 48);
+        assert_eq!(layout.total_arcs(7), 48);
+    }
+
+    #[test]
+    fn test_total_arcs_with_width_ratio_matches_every_ring() {
+        for (first_ring_arcs, ratio) in [(2, 1.0), (3, 1.2), (6, 1.5), (6, 2.0), (17, 3.7)] {
+            let layout = RingLayout::new(first_ring_arcs, Subdivision::WidthRatio(ratio)).unwrap();
+            let mut arcs = first_ring_arcs;
+            for circle in 2..2000 {
+                if calc_cell_width(circle, arcs) > ratio {
+                    arcs *= 2;
+                }
+                assert_eq!(layout.total_arcs(circle), arcs, "{:?} at {}", layout, circle);
+            }
+        }
+    }
+
+    #[test]
+    fn test_huge_rings_are_not_counted() {
+        let layout = RingLayout::default();
+        assert_eq!(layout.checked_total_arcs(usize::MAX), None);
+        assert_eq!(CircleCoord::checked_with_arc_index(2, usize::MAX, &layout), None);
+        assert_eq!(CircleCoord::checked_with_arc_index(2, 12, &layout), None);
+
+        let layout = RingLayout::new(6, Subdivision::WidthRatio(1.5)).unwrap();
+        assert!(layout.checked_total_arcs(4_000_000_000).is_some());
+        assert_eq!(layout.checked_total_arcs(usize::MAX), None);
+    }
+
+    #[test]
+    fn test_ring_layout_rejects_invalid_values() {
+        assert!(RingLayout::new(1, Subdivision::PowerOfTwo).is_err());
+        assert!(RingLayout::new(6, Subdivision::WidthRatio(0.5)).is_err());
+        assert!(RingLayout::new(6, Subdivision::WidthRatio(f64::NAN)).is_err());
+    }
+
+    #[test]
+    fn test_next_in_with_width_ratio() {
+        let layout = RingLayout::new(6, Subdivision::WidthRatio(1.5)).unwrap();
+
+        let coord = CircleCoord::create_with_arc_index(5, 17, &layout);
+        let prev = coord.next_in();
+        assert_eq!(prev.circle, 4);
+        assert_eq!(prev.arc_index, 17);
+
+        let coord = CircleCoord::create_with_arc_index(6, 35, &layout);
+        let prev = coord.next_in();
+        assert_eq!(prev.circle, 5);
+        assert_eq!(prev.arc_index, 17);
     }
 
     #[test]
     fn test_create_with_arc_index() {
-        let coord0 = CircleCoord::create_with_arc_index(0, 0);
+        let coord0 = CircleCoord::create_with_arc_index(0, 0, &RingLayout::default());
         assert_eq!(coord0.angle, Fraction::from(0));
 
-        let coord0_c1 = CircleCoord::create_with_arc_index(1, 0);
+        let coord0_c1 = CircleCoord::create_with_arc_index(1, 0, &RingLayout::default());
         assert_eq!(coord0_c1.angle, Fraction::from(0));
 
-        let coord1_c1 = CircleCoord::create_with_arc_index(1, 1);
+        let coord1_c1 = CircleCoord::create_with_arc_index(1, 1, &RingLayout::default());
         assert_eq!(coord1_c1.angle, Fraction::from(60));
 
-        let coord2_c1 = CircleCoord::create_with_arc_index(1, 2);
+        let coord2_c1 = CircleCoord::create_with_arc_index(1, 2, &RingLayout::default());
         assert_eq!(coord2_c1.angle, Fraction::from(120));
 
-        let coord3_c1 = CircleCoord::create_with_arc_index(1, 3);
+        let coord3_c1 = CircleCoord::create_with_arc_index(1, 3, &RingLayout::default());
         assert_eq!(coord3_c1.angle, Fraction::from(180));
 
-        let coord4_c1 = CircleCoord::create_with_arc_index(1, 4);
+        let coord4_c1 = CircleCoord::create_with_arc_index(1, 4, &RingLayout::default());
         assert_eq!(coord4_c1.angle, Fraction::from(240));
 
-        let coord5_c1 = CircleCoord::create_with_arc_index(1, 5);
+        let coord5_c1 = CircleCoord::create_with_arc_index(1, 5, &RingLayout::default());
         assert_eq!(coord5_c1.angle, Fraction::from(300));
 
-        let coord1_c10 = CircleCoord::create_with_arc_index(10, 1);
+        let coord1_c10 = CircleCoord::create_with_arc_index(10, 1, &RingLayout::default());
         assert_eq!(coord1_c10.angle, Fraction::new(360u64, 48u64));
     }
 
     #[test]
     fn test_create_with_fraction() {
-        let coord0 = CircleCoord::create_with_fraction(0, Fraction::from(0));
+        let layout = RingLayout::default();
+        let coord0 = CircleCoord::create_with_fraction(0, Fraction::from(0), &layout);
         assert_eq!(coord0.arc_index, 0);
     }
 
     #[test]
     fn test_next_clockwise() {
-        let coord0 = CircleCoord::create_with_arc_index(0, 0);
+        let coord0 = CircleCoord::create_with_arc_index(0, 0, &RingLayout::default());
         let next = coord0.next_clockwise();
         assert_eq!(next.circle, 0);
         assert_eq!(next.arc_index, 0);
         assert_eq!(next.angle, Fraction::from(0));
 
-        let coord2_0 = CircleCoord::create_with_arc_index(2, 0);
+        let coord2_0 = CircleCoord::create_with_arc_index(2, 0, &RingLayout::default());
         let next = coord2_0.next_clockwise();
         assert_eq!(next.circle, 2);
         assert_eq!(next.arc_index, 1);
         assert_eq!(next.angle, Fraction::from(30));
 
-        let coord2_11 = CircleCoord::create_with_arc_index(2, 11);
+        let coord2_11 = CircleCoord::create_with_arc_index(2, 11, &RingLayout::default());
         let next = coord2_11.next_clockwise();
         assert_eq!(next.circle, 2);
         assert_eq!(next.arc_index, 0);
         assert_eq!(next.angle, Fraction::from(0));
 
-        let coord4_23 = CircleCoord::create_with_arc_index(4, 23);
+        let coord4_23 = CircleCoord::create_with_arc_index(4, 23, &RingLayout::default());
         let next = coord4_23.next_clockwise();
         assert_eq!(next.circle, 4);
         assert_eq!(next.arc_index, 0);
@@ -210,25 +438,25 @@ mod tests {
 
     #[test]
     fn test_next_out() {
-        let coord0_0 = CircleCoord::create_with_arc_index(0, 0);
+        let coord0_0 = CircleCoord::create_with_arc_index(0, 0, &RingLayout::default());
         let next = coord0_0.next_out();
         assert_eq!(next.circle, 1);
         assert_eq!(next.arc_index, 0);
         assert_eq!(next.angle, Fraction::from(0));
 
-        let coord1_1 = CircleCoord::create_with_arc_index(1, 1);
+        let coord1_1 = CircleCoord::create_with_arc_index(1, 1, &RingLayout::default());
         let next = coord1_1.next_out();
         assert_eq!(next.circle, 2);
         assert_eq!(next.angle, Fraction::from(60));
         assert_eq!(next.arc_index, 2);
 
-        let coord2_4 = CircleCoord::create_with_arc_index(2, 4);
+        let coord2_4 = CircleCoord::create_with_arc_index(2, 4, &RingLayout::default());
         let next = coord2_4.next_out();
         assert_eq!(next.circle, 3);
         assert_eq!(next.angle, Fraction::from(120));
         assert_eq!(next.arc_index, 4);
 
-        let coord3_3 = CircleCoord::create_with_arc_index(3, 3);
+        let coord3_3 = CircleCoord::create_with_arc_index(3, 3, &RingLayout::default());
         let next = coord3_3.next_out();
         assert_eq!(next.circle, 4);
         assert_eq!(next.angle, Fraction::from(90));
@@ -237,25 +465,25 @@ mod tests {
 
     #[test]
     fn test_next_counter_clockwise() {
-        let coord0 = CircleCoord::create_with_arc_index(0, 0);
+        let coord0 = CircleCoord::create_with_arc_index(0, 0, &RingLayout::default());
         let prev = coord0.next_counter_clockwise();
         assert_eq!(prev.circle, 0);
         assert_eq!(prev.arc_index, 0);
         assert_eq!(prev.angle, Fraction::from(0));
 
-        let coord2_0 = CircleCoord::create_with_arc_index(2, 0);
+        let coord2_0 = CircleCoord::create_with_arc_index(2, 0, &RingLayout::default());
         let prev = coord2_0.next_counter_clockwise();
         assert_eq!(prev.circle, 2);
         assert_eq!(prev.arc_index, 11);
         assert_eq!(prev.angle, Fraction::from(330));
 
-        let coord2_5 = CircleCoord::create_with_arc_index(2, 5);
+        let coord2_5 = CircleCoord::create_with_arc_index(2, 5, &RingLayout::default());
         let prev = coord2_5.next_counter_clockwise();
         assert_eq!(prev.circle, 2);
         assert_eq!(prev.arc_index, 4);
         assert_eq!(prev.angle, Fraction::from(120));
 
-        let coord4_0 = CircleCoord::create_with_arc_index(4, 0);
+        let coord4_0 = CircleCoord::create_with_arc_index(4, 0, &RingLayout::default());
         let prev = coord4_0.next_counter_clockwise();
         assert_eq!(prev.circle, 4);
         assert_eq!(prev.arc_index, 23);
@@ -264,61 +492,61 @@ mod tests {
 
     #[test]
     fn test_next_in() {
-        let coord1_0 = CircleCoord::create_with_arc_index(1, 0);
+        let coord1_0 = CircleCoord::create_with_arc_index(1, 0, &RingLayout::default());
         let prev = coord1_0.next_in();
         assert_eq!(prev.circle, 0);
         assert_eq!(prev.arc_index, 0);
         assert_eq!(prev.angle, Fraction::from(0));
 
-        let coord1_1 = CircleCoord::create_with_arc_index(1, 1);
+        let coord1_1 = CircleCoord::create_with_arc_index(1, 1, &RingLayout::default());
         let prev = coord1_1.next_in();
         assert_eq!(prev.circle, 0);
         assert_eq!(prev.angle, Fraction::from(0));
         assert_eq!(prev.arc_index, 0);
 
-        let coord1_5 = CircleCoord::create_with_arc_index(1, 5);
+        let coord1_5 = CircleCoord::create_with_arc_index(1, 5, &RingLayout::default());
         let prev = coord1_5.next_in();
         assert_eq!(prev.circle, 0);
         assert_eq!(prev.angle, Fraction::from(0));
         assert_eq!(prev.arc_index, 0);
 
-        let coord2_0 = CircleCoord::create_with_arc_index(2, 0);
+        let coord2_0 = CircleCoord::create_with_arc_index(2, 0, &RingLayout::default());
         let prev = coord2_0.next_in();
         assert_eq!(prev.circle, 1);
         assert_eq!(prev.angle, Fraction::from(0));
         assert_eq!(prev.arc_index, 0);
 
-        let coord2_1 = CircleCoord::create_with_arc_index(2, 1);
+        let coord2_1 = CircleCoord::create_with_arc_index(2, 1, &RingLayout::default());
         let prev = coord2_1.next_in();
         assert_eq!(prev.circle, 1);
         assert_eq!(prev.angle, Fraction::from(0));
         assert_eq!(prev.arc_index, 0);
 
-        let coord2_2 = CircleCoord::create_with_arc_index(2, 2);
+        let coord2_2 = CircleCoord::create_with_arc_index(2, 2, &RingLayout::default());
         let prev = coord2_2.next_in();
         assert_eq!(prev.circle, 1);
         assert_eq!(prev.angle, Fraction::from(60));
         assert_eq!(prev.arc_index, 1);
 
-        let coord3_4 = CircleCoord::create_with_arc_index(3, 4);
+        let coord3_4 = CircleCoord::create_with_arc_index(3, 4, &RingLayout::default());
         let prev = coord3_4.next_in();
         assert_eq!(prev.circle, 2);
         assert_eq!(prev.angle, Fraction::from(120));
         assert_eq!(prev.arc_index, 4);
 
-        let coord3_5 = CircleCoord::create_with_arc_index(3, 5);
+        let coord3_5 = CircleCoord::create_with_arc_index(3, 5, &RingLayout::default());
         let prev = coord3_5.next_in();
         assert_eq!(prev.circle, 2);
         assert_eq!(prev.angle, Fraction::from(150));
         assert_eq!(prev.arc_index, 5);
 
-        let coord4_6 = CircleCoord::create_with_arc_index(4, 6);
+        let coord4_6 = CircleCoord::create_with_arc_index(4, 6, &RingLayout::default());
         let prev = coord4_6.next_in();
         assert_eq!(prev.circle, 3);
         assert_eq!(prev.angle, Fraction::from(90));
         assert_eq!(prev.arc_index, 3);
 
-        let coord4_7 = CircleCoord::create_with_arc_index(4, 7);
+        let coord4_7 = CircleCoord::create_with_arc_index(4, 7, &RingLayout::default());
         let prev = coord4_7.next_in();
         assert_eq!(prev.circle, 3);
         assert_eq!(prev.angle, Fraction::from(90));
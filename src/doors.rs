@@ -0,0 +1,281 @@
+use crate::circle_coord::{CircleCoord, RingLayout};
+use crate::error::MazeError;
+use crate::maze::{factory, Maze, Wall};
+use rand::{seq::IndexedRandom, Rng};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+pub const MAX_DOORS: usize = 8;
+
+/// A locked passage between two adjacent cells.
+#[derive(Debug, Clone)]
+pub struct Door {
+    cells: [CircleCoord; 2],
+}
+
+impl Door {
+    pub fn cells(&self) -> &[CircleCoord; 2] {
+        &self.cells
+    }
+
+    pub fn wall(&self) -> Wall {
+        Wall::between(&self.cells[0], &self.cells[1]).expect("Door cells must be adjacent")
+    }
+
+    fn connects(&self, a: &CircleCoord, b: &CircleCoord) -> bool {
+        let [x, y] = self.cells();
+        (x == a && y == b) || (x == b && y == a)
+    }
+}
+
+/// A maze with locked doors. Key `i` lies in `keys[i]` and opens door `i`; a key is picked up
+/// by entering its cell.
+#[derive(Debug)]
+pub struct DoorPuzzle {
+    maze: Maze,
+    start: CircleCoord,
+    goal: CircleCoord,
+    doors: Vec<Door>,
+    keys: Vec<CircleCoord>,
+}
+
+impl DoorPuzzle {
+    pub fn maze(&self) -> &Maze {
+        &self.maze
+    }
+
+    pub fn start(&self) -> &CircleCoord {
+        &self.start
+    }
+
+    pub fn goal(&self) -> &CircleCoord {
+        &self.goal
+    }
+
+    pub fn doors(&self) -> &[Door] {
+        &self.doors
+    }
+
+    pub fn keys(&self) -> &[CircleCoord] {
+        &self.keys
+    }
+
+    fn pick_up_keys(&self, coord: &CircleCoord, keys_held: u64) -> u64 {
+        self.keys
+            .iter()
+            .enumerate()
+            .filter(|(_, key)| *key == coord)
+            .fold(keys_held, |held, (i, _)| held | (1 << i))
+    }
+
+    /// The neighbours of `coord` that can be entered while holding the keys in `keys_held`, a
+    /// bit set indexed by door.
+    pub fn accessible_neighbours(&self, coord: &CircleCoord, keys_held: u64) -> Vec<CircleCoord> {
+        self.maze
+            .accessible_neighbours(coord)
+            .into_iter()
+            .filter(|neighbour| {
+                self.doors
+                    .iter()
+                    .enumerate()
+                    .all(|(i, door)| {
+                        keys_held & (1 << i) != 0 || !door.connects(coord, neighbour)
+                    })
+            })
+            .collect()
+    }
+
+    /// Finds the shortest walk from start to goal by searching over (cell, keys held) states.
+    /// The walk revisits cells where the player has to go back after fetching a key.
+    pub fn solve(&self) -> Option<Vec<CircleCoord>> {
+        type State = (CircleCoord, u64);
+
+        let initial: State = (self.start.clone(), self.pick_up_keys(&self.start, 0));
+
+        let mut queue = VecDeque::new();
+        let mut parent: HashMap<State, Option<State>> = HashMap::new();
+        let mut reached = None;
+
+        queue.push_back(initial.clone());
+        parent.insert(initial, None);
+
+        while let Some(current) = queue.pop_front() {
+            if current.0 == self.goal {
+                reached = Some(current);
+                break;
+            }
+
+            for neighbor in self.accessible_neighbours(&current.0, current.1) {
+                let keys_held = self.pick_up_keys(&neighbor, current.1);
+                let next = (neighbor, keys_held);
+                if !parent.contains_key(&next) {
+                    parent.insert(next.clone(), Some(current.clone()));
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        let mut path = Vec::new();
+        let mut node = Some(reached?);
+
+        while let Some(n) = node {
+            path.push(n.0.clone());
+            node = parent.get(&n).and_then(|p| p.clone());
+        }
+
+        path.reverse();
+        Some(path)
+    }
+}
+
+// The cells reachable from `start` without passing any of the given passages.
+fn reachable_cells(maze: &Maze, start: &CircleCoord, blocked: &[Door]) -> HashSet<CircleCoord> {
+    let mut visited = HashSet::new();
+    let mut stack = vec![start.clone()];
+
+    while let Some(current) = stack.pop() {
+        if !visited.insert(current.clone()) {
+            continue;
+        }
+        for neighbour in maze.accessible_neighbours(&current) {
+            if !blocked.iter().any(|door| door.connects(&current, &neighbour)) {
+                stack.push(neighbour);
+            }
+        }
+    }
+
+    visited
+}
+
+/// Generates a maze with up to `door_count` locked doors on the route between the ends of its
+/// longest path. The key of every door lies in a side branch before the door, so the player has
+/// to leave the route and come back to open it.
+pub fn door_factory<R: Rng>(
+    circles: usize,
+    layout: &RingLayout,
+    door_count: usize,
+    rng: &mut R,
+) -> Result<DoorPuzzle, MazeError> {
+    if door_count > MAX_DOORS {
+        return Err(MazeError::options(format!("at most {} doors are supported", MAX_DOORS)));
+    }
+    if circles < 2 {
+        return Err(MazeError::options("a door puzzle needs at least 2 circles"));
+    }
+
+    let maze = factory(circles, layout, rng);
+    let route = maze.tree_diameter();
+    // Every door sits between two cells of the route.
+    if route.len() < 2 {
+        return Err(MazeError::options("the maze has no route to put doors on"));
+    }
+    let start = route[0].clone();
+    let goal = route[route.len() - 1].clone();
+    let route_cells: HashSet<CircleCoord> = route.iter().cloned().collect();
+
+    let mut edges: Vec<usize> = (1..=door_count)
+        .map(|i| i * (route.len() - 1) / (door_count + 1))
+        .collect();
+    edges.dedup();
+
+    let planned: Vec<Door> = edges
+        .into_iter()
+        .map(|edge| Door {
+            cells: [route[edge].clone(), route[edge + 1].clone()],
+        })
+        .collect();
+
+    let mut doors = Vec::new();
+    let mut keys: Vec<CircleCoord> = Vec::new();
+    let mut previous_region = HashSet::new();
+
+    for i in 0..planned.len() {
+        let region = reachable_cells(&maze, &start, &planned[i..]);
+
+        let mut candidates: Vec<&CircleCoord> = region
+            .iter()
+            .filter(|c| !route_cells.contains(c) && !keys.contains(c))
+            .collect();
+        // Sorted, so that the same seed hides the keys in the same cells.
+        candidates.sort();
+        let fresh: Vec<&CircleCoord> = candidates
+            .iter()
+            .copied()
+            .filter(|c| !previous_region.contains(*c))
+            .collect();
+
+        let key = fresh.choose(rng).or_else(|| candidates.choose(rng));
+        if let Some(key) = key {
+            keys.push((*key).clone());
+            doors.push(planned[i].clone());
+        }
+
+        previous_region = region;
+    }
+
+    Ok(DoorPuzzle {
+        maze,
+        start,
+        goal,
+        doors,
+        keys,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_door_puzzle_is_solvable_and_needs_backtracking() {
+        for seed in [42, 123, 456, 789] {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let puzzle = door_factory(8, &RingLayout::default(), 3, &mut rng).unwrap();
+            assert_eq!(puzzle.doors().len(), 3, "Seed {} placed too few doors", seed);
+
+            let path = puzzle.solve().expect("Puzzle should be solvable");
+            assert_eq!(path.first(), Some(puzzle.start()));
+            assert_eq!(path.last(), Some(puzzle.goal()));
+
+            for key in puzzle.keys() {
+                assert!(path.contains(key), "Seed {}: solution skips key {:?}", seed, key);
+            }
+
+            let distinct: HashSet<_> = path.iter().collect();
+            assert!(distinct.len() < path.len(), "Seed {}: solution never backtracks", seed);
+        }
+    }
+
+    #[test]
+    fn test_doors_block_without_key() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let puzzle = door_factory(8, &RingLayout::default(), 2, &mut rng).unwrap();
+        let door = &puzzle.doors()[0];
+        let [a, b] = door.cells();
+
+        assert!(!puzzle.accessible_neighbours(a, 0).contains(b));
+        assert!(puzzle.accessible_neighbours(a, 1).contains(b));
+    }
+
+    #[test]
+    fn test_door_factory_rejects_too_many_doors() {
+        let mut rng = StdRng::seed_from_u64(42);
+        assert!(door_factory(8, &RingLayout::default(), MAX_DOORS + 1, &mut rng).is_err());
+    }
+
+    #[test]
+    fn test_door_factory_rejects_single_circle() {
+        let mut rng = StdRng::seed_from_u64(42);
+        assert!(door_factory(1, &RingLayout::default(), 1, &mut rng).is_err());
+    }
+
+    #[test]
+    fn test_same_seed_gives_same_keys() {
+        let generate = || {
+            let mut rng = StdRng::seed_from_u64(9);
+            door_factory(8, &RingLayout::default(), 3, &mut rng).unwrap().keys().to_vec()
+        };
+        assert_eq!(generate(), generate());
+    }
+}
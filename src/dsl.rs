@@ -0,0 +1,237 @@
+use crate::circle_coord::{CircleCoord, RingLayout, Subdivision};
+use crate::error::MazeError;
+use crate::maze::{Maze, MazeDeserializer, Wall};
+
+const ARC_WALL: char = '_';
+const LINE_WALL: char = '|';
+const OPEN: char = '.';
+
+/// Writes the walls of a maze as text, one ring per line from the centre outwards:
+///
+/// ```text
+/// ring 1  arcs ._._._        lines |||||.
+/// ring 2  arcs .._._._.___.  lines ||.|......|.
+/// ring 3  arcs ____________
+/// ```
+///
+/// Each ring lists the arc on the inner side of every cell, `_` for a wall and `.` for an
+/// opening, then the line on the counter-clockwise side of every cell, `|` for a wall. The last
+/// ring is the outer boundary and has only arcs. A `layout` line comes first if the maze does
+/// not use the default ring layout. Like share codes, the text only holds walls, so mazes with
+/// more are refused.
+pub fn write_dsl(maze: &Maze) -> Result<String, MazeError> {
+    if !maze.has_only_walls() {
+        return Err(MazeError::options(
+            "maze text only holds walls, not crossings, one-way passages, portals or costs",
+        ));
+    }
+
+    let layout = maze.layout();
+    let mut content = String::new();
+    if layout != &RingLayout::default() {
+        content.push_str(&format!("layout {}", layout.first_ring_arcs()));
+        if let Subdivision::WidthRatio(ratio) = layout.subdivision() {
+            content.push_str(&format!(" ratio {}", ratio));
+        }
+        content.push('\n');
+    }
+
+    let ring = |circle: usize, wall: char, walls: &dyn Fn(&CircleCoord) -> bool| -> String {
+        (0..layout.total_arcs(circle))
+            .map(|arc_index| CircleCoord::create_with_arc_index(circle, arc_index, layout))
+            .map(|coord| if walls(&coord) { wall } else { OPEN })
+            .collect()
+    };
+    let width = layout.total_arcs(maze.circles().saturating_sub(1));
+    for circle in 1..=maze.circles() {
+        let arcs = ring(circle, ARC_WALL, &|coord| maze.arcs().contains(coord));
+        if circle < maze.circles() {
+            let lines = ring(circle, LINE_WALL, &|coord| maze.lines().contains(coord));
+            let row = format!("ring {:<2} arcs {:<width$}  lines {}\n", circle, arcs, lines);
+            content.push_str(&row);
+        } else {
+            content.push_str(&format!("ring {:<2} arcs {}\n", circle, arcs));
+        }
+    }
+    Ok(content)
+}
+
+/// Builds the maze that text in the format of `write_dsl` describes. Walls may be split into
+/// groups with spaces, and everything after a `#` is a comment.
+pub fn read_dsl(text: &str) -> Result<Maze, MazeError> {
+    let (circles, layout, walls) = parse_dsl(text)?;
+    Ok(MazeDeserializer::from_walls(circles, &layout, walls))
+}
+
+// The size, layout and walls of the maze the text describes.
+fn parse_dsl(text: &str) -> Result<(usize, RingLayout, Vec<Wall>), MazeError> {
+    let mut layout = None;
+    // The line number and the arcs and lines of every ring read so far.
+    let mut rings: Vec<(usize, String, Option<String>)> = Vec::new();
+
+    for (i, line) in text.lines().enumerate() {
+        let number = i + 1;
+        let error = |message: String| MazeError::Dsl { line: number, message };
+        let line = line.split('#').next().unwrap_or_default();
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+
+        match tokens.as_slice() {
+            [] => {}
+            ["layout", rest @ ..] => {
+                if layout.is_some() || !rings.is_empty() {
+                    let message = String::from("the layout must come once, before the rings");
+                    return Err(error(message));
+                }
+                layout = Some(parse_layout(rest).map_err(error)?);
+            }
+            ["ring", circle, "arcs", rest @ ..] => {
+                let expected = rings.len() + 1;
+                if circle.parse() != Ok(expected) {
+                    let message = format!("expected ring {}, found ring {}", expected, circle);
+                    return Err(error(message));
+                }
+                let (arcs, lines) = match rest.iter().position(|token| *token == "lines") {
+                    Some(split) => (rest[..split].concat(), Some(rest[split + 1..].concat())),
+                    None => (rest.concat(), None),
+                };
+                rings.push((number, arcs, lines));
+            }
+            _ => {
+                let message = String::from("expected `layout` or `ring <number> arcs`");
+                return Err(error(message));
+            }
+        }
+    }
+
+    let layout = layout.unwrap_or_default();
+    let circles = rings.len();
+    if circles == 0 {
+        return Err(MazeError::Dsl { line: 1, message: String::from("the maze has no rings") });
+    }
+
+    let mut walls = Vec::new();
+    for (circle, (number, arcs, lines)) in (1..).zip(rings) {
+        let error = |message: String| MazeError::Dsl { line: number, message };
+        let arcs = read_ring(circle, &arcs, ARC_WALL, &layout).map_err(error)?;
+        walls.extend(arcs.into_iter().map(Wall::Arc));
+        match (lines, circle < circles) {
+            (Some(lines), true) => {
+                let lines = read_ring(circle, &lines, LINE_WALL, &layout).map_err(error)?;
+                walls.extend(lines.into_iter().map(Wall::Line));
+            }
+            (None, true) => return Err(error(format!("ring {} has no lines", circle))),
+            (Some(_), false) => {
+                return Err(error(String::from("the outer boundary cannot have lines")));
+            }
+            (None, false) => {}
+        }
+    }
+    Ok((circles, layout, walls))
+}
+
+fn parse_layout(tokens: &[&str]) -> Result<RingLayout, String> {
+    let subdivision = match tokens {
+        [_] => Subdivision::PowerOfTwo,
+        [_, "ratio", ratio] => {
+            let ratio = ratio.parse().map_err(|_| format!("'{}' is not a ratio", ratio))?;
+            Subdivision::WidthRatio(ratio)
+        }
+        _ => return Err(String::from("expected `layout <arcs>` or `layout <arcs> ratio <r>`")),
+    };
+    let first_ring_arcs =
+        tokens[0].parse().map_err(|_| format!("'{}' is not a number of arcs", tokens[0]))?;
+    RingLayout::new(first_ring_arcs, subdivision).map_err(|error| error.to_string())
+}
+
+// The coordinates of the walls in one ring of `wall` and `.` characters.
+fn read_ring(
+    circle: usize,
+    cells: &str,
+    wall: char,
+    layout: &RingLayout,
+) -> Result<Vec<CircleCoord>, String> {
+    let (found, total_arcs) = (cells.chars().count(), layout.total_arcs(circle));
+    if found != total_arcs {
+        return Err(format!("ring {} has {} cells, not {}", circle, total_arcs, found));
+    }
+    if let Some(other) = cells.chars().find(|c| *c != wall && *c != OPEN) {
+        return Err(format!("expected '{}' or '{}', found '{}'", wall, OPEN, other));
+    }
+
+    Ok(cells
+        .chars()
+        .enumerate()
+        .filter(|(_, c)| *c == wall)
+        .map(|(arc_index, _)| CircleCoord::create_with_arc_index(circle, arc_index, layout))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::factory;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_text_round_trips() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let layout = RingLayout::new(5, Subdivision::WidthRatio(1.5)).unwrap();
+        let maze = factory(8, &layout, &mut rng);
+
+        let text = write_dsl(&maze).unwrap();
+        assert!(text.starts_with("layout 5 ratio 1.5\nring 1  arcs "));
+
+        let parsed = read_dsl(&text).unwrap();
+        assert_eq!(parsed.circles(), maze.circles());
+        assert_eq!(parsed.layout(), maze.layout());
+        assert_eq!(parsed.arcs(), maze.arcs());
+        assert_eq!(parsed.lines(), maze.lines());
+    }
+
+    #[test]
+    fn test_text_allows_comments_and_groups() {
+        let text = "
+            # The centre opens to the first cell of ring 1.
+            ring 1 arcs .__ ___  lines ||| |||
+            ring 2 arcs ______ ______  # the outer boundary
+        ";
+        let (circles, layout, walls) = parse_dsl(text).unwrap();
+
+        assert_eq!((circles, layout), (2, RingLayout::default()));
+        assert_eq!(walls.len(), 5 + 6 + 12);
+        let opening = CircleCoord::create_with_arc_index(1, 0, &layout);
+        assert!(!walls.contains(&Wall::Arc(opening)));
+    }
+
+    #[test]
+    fn test_mistakes_are_reported_with_their_line() {
+        let cases = [
+            ("ring 1 arcs ______ lines ||||||\nring 2 arcs _____", 2, "ring 2 has 12 cells, not 5"),
+            ("ring 1 arcs ______\nring 2 arcs ____________", 1, "ring 1 has no lines"),
+            ("ring 1 arcs ______ lines ||||||", 1, "the outer boundary cannot have lines"),
+            ("ring 2 arcs ____________", 1, "expected ring 1, found ring 2"),
+            ("ring 1 arcs ___|__", 1, "expected '_' or '.', found '|'"),
+            ("\n\nlayout 1", 3, "first ring must have at least 2 arcs"),
+            ("walls ______", 1, "expected `layout` or `ring <number> arcs`"),
+            ("# nothing here", 1, "the maze has no rings"),
+        ];
+        for (text, line, message) in cases {
+            match parse_dsl(text) {
+                Err(MazeError::Dsl { line: found_line, message: found_message }) => {
+                    assert_eq!((found_line, found_message.as_str()), (line, message), "{}", text);
+                }
+                other => panic!("Expected an error for {:?}, got {:?}", text, other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_mazes_with_more_than_walls_have_no_text() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut maze = factory(4, &RingLayout::default(), &mut rng);
+        maze.set_cost(CircleCoord::create_with_arc_index(1, 0, maze.layout()), 3);
+
+        assert_eq!(write_dsl(&maze).unwrap_err().name(), "OptionsError");
+    }
+}
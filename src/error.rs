@@ -0,0 +1,235 @@
+use std::fmt;
+
+/// Everything that can go wrong when loading a saved maze, from reading the file to the maze it
+/// describes, or when generating one from options that cannot be honoured.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MazeError {
+    /// The file could not be read.
+    Io { file: String, message: String },
+    /// The text is not valid JSON.
+    Syntax { line: usize, column: usize, message: String },
+    /// The JSON does not have the shape of a saved maze. `path` names the offending value, such
+    /// as `arcs[3].circle`, and is empty for the document itself. It is not part of the message,
+    /// so callers can show it however suits them.
+    Schema { path: String, message: String },
+    /// The file is well-formed but describes an impossible maze.
+    Semantic(Vec<Diagnostic>),
+    /// A share code that is mistyped, cut off or from an unknown version, or a maze with more
+    /// than walls, which no share code can hold.
+    ShareCode(String),
+    /// An SVG that cannot be read or does not carry a maze.
+    Svg(String),
+    /// Maze text in the format of `dsl::write_dsl` that cannot be read.
+    Dsl { line: usize, message: String },
+    /// Generator or output options that cannot be honoured, such as an unknown solver, a
+    /// symmetry the ring layout does not allow or maze text for a maze with portals.
+    Options(String),
+}
+
+impl MazeError {
+    pub fn schema(path: &str, message: impl Into<String>) -> Self {
+        MazeError::Schema { path: String::from(path), message: message.into() }
+    }
+
+    pub fn options(message: impl Into<String>) -> Self {
+        MazeError::Options(message.into())
+    }
+
+    /// A short name for the kind of error, for callers that handle them differently.
+    pub fn name(&self) -> &'static str {
+        match self {
+            MazeError::Io { .. } => "IoError",
+            MazeError::Syntax { .. } => "JsonSyntaxError",
+            MazeError::Schema { .. } => "SchemaError",
+            MazeError::Semantic(_) => "SemanticError",
+            MazeError::ShareCode(_) => "ShareCodeError",
+            MazeError::Svg(_) => "SvgError",
+            MazeError::Dsl { .. } => "DslError",
+            MazeError::Options(_) => "OptionsError",
+        }
+    }
+
+    /// The JSON path of the first offending value, if the error points to one.
+    pub fn path(&self) -> Option<&str> {
+        match self {
+            MazeError::Schema { path, .. } => Some(path),
+            MazeError::Semantic(diagnostics) => diagnostics.iter().find_map(|d| d.path()),
+            MazeError::Io { .. }
+            | MazeError::Syntax { .. }
+            | MazeError::ShareCode(_)
+            | MazeError::Svg(_)
+            | MazeError::Dsl { .. }
+            | MazeError::Options(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for MazeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MazeError::Io { file, message } => write!(f, "{}: {}", file, message),
+            MazeError::Syntax { line, column, message } => {
+                write!(f, "invalid JSON at line {}, column {}: {}", line, column, message)
+            }
+            MazeError::Schema { message, .. } => write!(f, "{}", message),
+            MazeError::Semantic(diagnostics) => {
+                let messages: Vec<&str> = diagnostics.iter().map(|d| d.message()).collect();
+                write!(f, "invalid maze: {}", messages.join("; "))
+            }
+            MazeError::ShareCode(message) => write!(f, "invalid share code: {}", message),
+            MazeError::Svg(message) => write!(f, "cannot read the maze from the SVG: {}", message),
+            MazeError::Dsl { line, message } => {
+                write!(f, "invalid maze text at line {}: {}", line, message)
+            }
+            MazeError::Options(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for MazeError {}
+
+impl From<serde_json::Error> for MazeError {
+    fn from(error: serde_json::Error) -> Self {
+        // serde_json appends the position to its message, which is kept separately here.
+        let message = error.to_string();
+        let message = match message.rsplit_once(" at line ") {
+            Some((message, _)) => String::from(message),
+            None => message,
+        };
+        MazeError::Syntax { line: error.line(), column: error.column(), message }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagnosticKind {
+    /// The maze has no circles, so not even the centre cell exists.
+    Empty,
+    /// The maze has more circles or cells than can be checked in reasonable time.
+    TooLarge,
+    /// A value outside its allowed range, such as a zero cost or an impossible layout.
+    InvalidValue,
+    /// A one-way passage between cells that do not touch, or a step of a saved solution that
+    /// no passage allows.
+    NotAdjacent,
+    /// An entry names a cell the maze does not have.
+    OutOfRange,
+    /// An entry repeats an earlier one.
+    Duplicate,
+    /// A wall on a side of the centre cell, which has neither an inner arc nor a line.
+    ExtraWall,
+    /// The outer boundary has gaps.
+    MissingBoundary,
+    /// Some cells cannot be reached from the centre.
+    Unreachable,
+    /// A passage closes a loop, so there is more than one route between some cells.
+    Cycle,
+}
+
+/// A problem found in a saved maze. `path` points to the offending entry of the file, such as
+/// `arcs[3]`, when there is a single one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    kind: DiagnosticKind,
+    path: Option<String>,
+    message: String,
+}
+
+impl Diagnostic {
+    pub fn new(kind: DiagnosticKind, path: Option<String>, message: String) -> Self {
+        Diagnostic { kind, path, message }
+    }
+
+    pub fn kind(&self) -> DiagnosticKind {
+        self.kind
+    }
+
+    pub fn path(&self) -> Option<&str> {
+        self.path.as_deref()
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Loops are deliberate in weave, terrain and portal mazes, so they only warrant a warning.
+    /// Everything else makes the maze render wrongly or not at all.
+    pub fn severity(&self) -> Severity {
+        match self.kind {
+            DiagnosticKind::Cycle => Severity::Warning,
+            _ => Severity::Error,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circle_coord::{RingLayout, Subdivision};
+    use crate::maze::{symmetric_factory, MazeDeserializer};
+    use crate::solvers::Solver;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use serde_json::json;
+
+    #[test]
+    fn test_syntax_errors_point_to_the_position() {
+        let syntax_error = serde_json::from_str::<serde_json::Value>("{\n  \"circles\": ,");
+        let error = MazeError::from(syntax_error.unwrap_err());
+        assert!(matches!(error, MazeError::Syntax { line: 2, column: 14, .. }));
+        assert_eq!(error.name(), "JsonSyntaxError");
+        assert_eq!(error.to_string(), "invalid JSON at line 2, column 14: expected value");
+    }
+
+    #[test]
+    fn test_schema_errors_name_the_offending_value() {
+        let data = json!({
+            "circles": 3,
+            "arcs": [{ "circle": 1, "arc": 0 }, { "circle": "one", "arc": 1 }],
+            "lines": []
+        });
+        let error = MazeDeserializer::deserialize(data).unwrap_err();
+
+        assert_eq!(error.path(), Some("arcs[1].circle"));
+        assert!(error.to_string().starts_with("invalid type"));
+    }
+
+    #[test]
+    fn test_semantic_errors_list_every_problem() {
+        let data = json!({
+            "circles": 3,
+            "arcs": [],
+            "lines": [],
+            "one_way": [{ "from": { "circle": 1, "arc": 0 }, "to": { "circle": 2, "arc": 5 } }],
+            "costs": [{ "circle": 1, "arc": 0, "cost": 0 }]
+        });
+        let MazeError::Semantic(diagnostics) = MazeDeserializer::deserialize(data).unwrap_err()
+        else {
+            panic!("Expected a semantic error");
+        };
+
+        let paths: Vec<Option<&str>> = diagnostics.iter().map(|d| d.path()).collect();
+        assert_eq!(paths, [Some("one_way[0]"), Some("costs[0].cost")]);
+    }
+
+    #[test]
+    fn test_options_that_cannot_be_honoured_share_one_error() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let layout = RingLayout::default();
+        let errors = [
+            RingLayout::new(1, Subdivision::PowerOfTwo).unwrap_err(),
+            symmetric_factory(4, &layout, 5, &mut rng).unwrap_err(),
+            Solver::from_name("astar").unwrap_err(),
+        ];
+
+        for error in errors {
+            assert_eq!(error.name(), "OptionsError");
+            assert_eq!(error.path(), None);
+        }
+    }
+}
@@ -0,0 +1,319 @@
+use crate::circle_coord::{CircleCoord, RingLayout, Subdivision, DEFAULT_FIRST_RING_ARCS};
+use crate::error::MazeError;
+use crate::maze::TunnelAxis;
+use crate::metadata::Metadata;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+/// The version written into every saved maze. Files from before versioning count as version 0.
+pub const FORMAT_VERSION: u64 = 2;
+
+/// The published JSON Schema of the current format.
+pub const MAZE_SCHEMA: &str = include_str!("../schema/maze.schema.json");
+
+// Each entry upgrades a file from the version of its index to the next version.
+const MIGRATIONS: [fn(&mut Map<String, Value>); FORMAT_VERSION as usize] =
+    [add_default_layout, allow_metadata];
+
+/// A saved maze as it is stored in JSON.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MazeFile {
+    pub format_version: u64,
+    pub circles: usize,
+    pub layout: LayoutFile,
+    pub arcs: Vec<CellFile>,
+    pub lines: Vec<CellFile>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub crossings: Vec<CrossingFile>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub one_way: Vec<OneWayFile>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub portals: Vec<[CellFile; 2]>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub costs: Vec<CostFile>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<MetadataFile>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LayoutFile {
+    pub first_ring_arcs: usize,
+    #[serde(flatten)]
+    pub subdivision: SubdivisionFile,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "subdivision", rename_all = "snake_case")]
+pub enum SubdivisionFile {
+    PowerOfTwo,
+    WidthRatio { ratio: f64 },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CellFile {
+    pub circle: usize,
+    pub arc: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CrossingFile {
+    #[serde(flatten)]
+    pub cell: CellFile,
+    pub tunnel: TunnelAxis,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OneWayFile {
+    pub from: CellFile,
+    pub to: CellFile,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CostFile {
+    #[serde(flatten)]
+    pub cell: CellFile,
+    pub cost: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MetadataFile {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub algorithm: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub generator_version: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start: Option<CellFile>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub goal: Option<CellFile>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub solution: Option<Vec<CellFile>>,
+}
+
+impl MazeFile {
+    /// Every cell the file names, with its path in the file such as `one_way[2].to`.
+    pub fn cells(&self) -> Vec<(String, &CellFile)> {
+        let lists: [(&str, Vec<&CellFile>); 4] = [
+            ("arcs", self.arcs.iter().collect()),
+            ("lines", self.lines.iter().collect()),
+            ("crossings", self.crossings.iter().map(|c| &c.cell).collect()),
+            ("costs", self.costs.iter().map(|c| &c.cell).collect()),
+        ];
+        let mut cells = Vec::new();
+        for (name, entries) in lists {
+            let paths = (0..).map(|i| format!("{}[{}]", name, i));
+            cells.extend(paths.zip(entries));
+        }
+        for (i, passage) in self.one_way.iter().enumerate() {
+            cells.push((format!("one_way[{}].from", i), &passage.from));
+            cells.push((format!("one_way[{}].to", i), &passage.to));
+        }
+        for (i, [a, b]) in self.portals.iter().enumerate() {
+            cells.push((format!("portals[{}][0]", i), a));
+            cells.push((format!("portals[{}][1]", i), b));
+        }
+        if let Some(metadata) = &self.metadata {
+            cells.extend(metadata.start.iter().map(|cell| (String::from("metadata.start"), cell)));
+            cells.extend(metadata.goal.iter().map(|cell| (String::from("metadata.goal"), cell)));
+            let solution = metadata.solution.iter().flatten().enumerate();
+            cells.extend(solution.map(|(i, cell)| (format!("metadata.solution[{}]", i), cell)));
+        }
+        cells
+    }
+}
+
+impl LayoutFile {
+    pub fn to_layout(&self) -> Result<RingLayout, MazeError> {
+        let subdivision = match self.subdivision {
+            SubdivisionFile::PowerOfTwo => Subdivision::PowerOfTwo,
+            SubdivisionFile::WidthRatio { ratio } => Subdivision::WidthRatio(ratio),
+        };
+        RingLayout::new(self.first_ring_arcs, subdivision)
+    }
+}
+
+impl From<&RingLayout> for LayoutFile {
+    fn from(layout: &RingLayout) -> Self {
+        let subdivision = match layout.subdivision() {
+            Subdivision::PowerOfTwo => SubdivisionFile::PowerOfTwo,
+            Subdivision::WidthRatio(ratio) => SubdivisionFile::WidthRatio { ratio },
+        };
+        LayoutFile { first_ring_arcs: layout.first_ring_arcs(), subdivision }
+    }
+}
+
+impl CellFile {
+    /// The coordinate of the cell, or `None` if the layout has no such cell.
+    pub fn to_coord(&self, layout: &RingLayout) -> Option<CircleCoord> {
+        CircleCoord::checked_with_arc_index(self.circle, self.arc, layout)
+    }
+}
+
+impl From<&CircleCoord> for CellFile {
+    fn from(coord: &CircleCoord) -> Self {
+        CellFile { circle: coord.circle(), arc: coord.arc_index() }
+    }
+}
+
+impl MetadataFile {
+    /// Its cells must have been checked against the layout already.
+    pub fn to_metadata(&self, layout: &RingLayout) -> Metadata {
+        let to_coord =
+            |cell: &CellFile| cell.to_coord(layout).expect("The cell lies outside the layout");
+        Metadata {
+            title: self.title.clone(),
+            author: self.author.clone(),
+            created: self.created.clone(),
+            seed: self.seed,
+            algorithm: self.algorithm.clone(),
+            generator_version: self.generator_version.clone(),
+            start: self.start.as_ref().map(to_coord),
+            goal: self.goal.as_ref().map(to_coord),
+            solution: self
+                .solution
+                .as_ref()
+                .map(|cells| cells.iter().map(to_coord).collect()),
+        }
+    }
+}
+
+impl From<&Metadata> for MetadataFile {
+    fn from(metadata: &Metadata) -> Self {
+        MetadataFile {
+            title: metadata.title.clone(),
+            author: metadata.author.clone(),
+            created: metadata.created.clone(),
+            seed: metadata.seed,
+            algorithm: metadata.algorithm.clone(),
+            generator_version: metadata.generator_version.clone(),
+            start: metadata.start.as_ref().map(CellFile::from),
+            goal: metadata.goal.as_ref().map(CellFile::from),
+            solution: metadata
+                .solution
+                .as_ref()
+                .map(|route| route.iter().map(CellFile::from).collect()),
+        }
+    }
+}
+
+/// Upgrades a saved maze of any earlier format version to the current one.
+pub fn migrate(mut data: Value) -> Result<Value, MazeError> {
+    let version = match data.get("format_version") {
+        Some(version) => version
+            .as_u64()
+            .ok_or(MazeError::schema("format_version", "must be a number"))?,
+        None => 0,
+    };
+    if version > FORMAT_VERSION {
+        let message =
+            format!("version {} is newer than the supported version {}", version, FORMAT_VERSION);
+        return Err(MazeError::schema("format_version", message));
+    }
+
+    let obj = data
+        .as_object_mut()
+        .ok_or(MazeError::schema("", "a maze must be a JSON object"))?;
+    for migration in &MIGRATIONS[version as usize..] {
+        migration(obj);
+    }
+    obj.insert(String::from("format_version"), Value::from(FORMAT_VERSION));
+    Ok(data)
+}
+
+// Files from before versioning may leave out the layout, which then was always the default one.
+fn add_default_layout(obj: &mut Map<String, Value>) {
+    obj.entry("layout").or_insert_with(|| {
+        json!({ "first_ring_arcs": DEFAULT_FIRST_RING_ARCS, "subdivision": "power_of_two" })
+    });
+}
+
+// Version 2 added the optional `metadata` block. Older files have none, and a maze without
+// metadata is saved without it, so they need no changes; the bump tells older readers apart.
+fn allow_metadata(_obj: &mut Map<String, Value>) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_migrate_adds_version_and_default_layout() {
+        let data = json!({ "circles": 3, "arcs": [], "lines": [] });
+        let migrated = migrate(data).unwrap();
+
+        assert_eq!(migrated["format_version"], json!(FORMAT_VERSION));
+        assert_eq!(
+            migrated["layout"],
+            json!({ "first_ring_arcs": 6, "subdivision": "power_of_two" })
+        );
+    }
+
+    #[test]
+    fn test_migrate_upgrades_version_1_files_without_metadata() {
+        let data = json!({
+            "format_version": 1,
+            "circles": 3,
+            "layout": { "first_ring_arcs": 6, "subdivision": "power_of_two" },
+            "arcs": [],
+            "lines": []
+        });
+        let migrated = migrate(data.clone()).unwrap();
+
+        assert_eq!(migrated["format_version"], json!(FORMAT_VERSION));
+        assert_eq!(migrated["layout"], data["layout"]);
+        assert!(migrated.get("metadata").is_none());
+    }
+
+    #[test]
+    fn test_migrate_keeps_current_files_unchanged() {
+        let data = json!({
+            "format_version": FORMAT_VERSION,
+            "circles": 3,
+            "layout": { "first_ring_arcs": 8, "subdivision": "width_ratio", "ratio": 1.5 },
+            "arcs": [],
+            "lines": []
+        });
+        assert_eq!(migrate(data.clone()).unwrap(), data);
+    }
+
+    #[test]
+    fn test_migrate_rejects_newer_versions() {
+        let data = json!({ "format_version": FORMAT_VERSION + 1, "circles": 3 });
+        assert!(migrate(data).is_err());
+    }
+
+    #[test]
+    fn test_every_fixture_migrates_to_the_current_format() {
+        for entry in fs::read_dir("tests/fixtures").expect("Failed to read fixtures") {
+            let path = entry.unwrap().path();
+            let content = fs::read_to_string(&path).unwrap();
+            let data: Value = serde_json::from_str(&content).unwrap();
+
+            let migrated = migrate(data).unwrap();
+            let file: MazeFile = serde_json::from_value(migrated)
+                .unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+            assert_eq!(file.format_version, FORMAT_VERSION);
+        }
+    }
+
+    #[test]
+    fn test_schema_describes_the_current_version() {
+        let schema: Value = serde_json::from_str(MAZE_SCHEMA).expect("Schema must be valid JSON");
+        assert_eq!(schema["properties"]["format_version"]["const"], json!(FORMAT_VERSION));
+
+        let required: Vec<&str> = schema["required"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|name| name.as_str().unwrap())
+            .collect();
+        assert_eq!(required, ["format_version", "circles", "layout", "arcs", "lines"]);
+    }
+}
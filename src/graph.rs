@@ -0,0 +1,331 @@
+use crate::circle_coord::CircleCoord;
+use crate::error::MazeError;
+use crate::maze::Maze;
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// The file formats the passage graph of a maze can be exported in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphFormat {
+    /// Graphviz DOT.
+    Dot,
+    /// GraphML, as read by Gephi and networkx.
+    GraphMl,
+    /// A JSON object with the neighbours of every cell.
+    Json,
+}
+
+impl GraphFormat {
+    pub fn from_name(name: &str) -> Result<Self, MazeError> {
+        match name {
+            "dot" => Ok(GraphFormat::Dot),
+            "graphml" => Ok(GraphFormat::GraphMl),
+            "json" => Ok(GraphFormat::Json),
+            other => Err(MazeError::options(format!("Unknown graph format '{}'", other))),
+        }
+    }
+}
+
+/// Every cell of the maze and where it leads, as `Maze::accessible_neighbours` sees it. Cells
+/// and neighbours are sorted, so equal mazes give equal graphs.
+struct PassageGraph<'a> {
+    maze: &'a Maze,
+    // One-way passages can only be told apart in a directed graph; other mazes list every
+    // passage once.
+    directed: bool,
+    neighbours: BTreeMap<CircleCoord, BTreeSet<CircleCoord>>,
+    solution: &'a [CircleCoord],
+}
+
+impl<'a> PassageGraph<'a> {
+    fn new(maze: &'a Maze, solution: &'a [CircleCoord]) -> Self {
+        let layout = maze.layout();
+        let neighbours = (0..maze.circles())
+            .flat_map(|c| {
+                (0..layout.total_arcs(c))
+                    .map(move |arc_index| CircleCoord::create_with_arc_index(c, arc_index, layout))
+            })
+            .map(|cell| {
+                let neighbours = maze.accessible_neighbours(&cell);
+                let neighbours = neighbours.into_iter().filter(|n| n != &cell).collect();
+                (cell, neighbours)
+            })
+            .collect();
+        PassageGraph { maze, directed: !maze.one_way().is_empty(), neighbours, solution }
+    }
+
+    fn edges(&self) -> Vec<(&CircleCoord, &CircleCoord)> {
+        self.neighbours
+            .iter()
+            .flat_map(|(cell, neighbours)| neighbours.iter().map(move |n| (cell, n)))
+            .filter(|(cell, neighbour)| self.directed || cell < neighbour)
+            .collect()
+    }
+
+    fn on_solution(&self, cell: &CircleCoord) -> bool {
+        self.solution.contains(cell)
+    }
+
+    fn solution_edge(&self, from: &CircleCoord, to: &CircleCoord) -> bool {
+        self.solution.windows(2).any(|step| {
+            let forward = (&step[0], &step[1]) == (from, to);
+            forward || !self.directed && (&step[1], &step[0]) == (from, to)
+        })
+    }
+
+    fn portal_edge(&self, from: &CircleCoord, to: &CircleCoord) -> bool {
+        self.maze.portals().iter().any(|[a, b]| (a, b) == (from, to) || (b, a) == (from, to))
+    }
+}
+
+/// Writes the passages of a maze as a graph whose nodes are its cells, named `circle,arc`. The
+/// cells and passages of `solution` are marked, unless it is empty. Passages through portals
+/// are marked too, and the cost of every cell is given if the maze has costs.
+pub fn export_graph(maze: &Maze, format: GraphFormat, solution: &[CircleCoord]) -> String {
+    let graph = PassageGraph::new(maze, solution);
+    match format {
+        GraphFormat::Dot => write_dot(&graph),
+        GraphFormat::GraphMl => write_graphml(&graph),
+        GraphFormat::Json => {
+            let value = graph_json(&graph);
+            serde_json::to_string_pretty(&value).expect("Failed to serialize the graph")
+        }
+    }
+}
+
+fn cell_id(cell: &CircleCoord) -> String {
+    format!("{},{}", cell.circle(), cell.arc_index())
+}
+
+fn write_dot(graph: &PassageGraph) -> String {
+    let (kind, connector) = if graph.directed { ("digraph", "->") } else { ("graph", "--") };
+    let mut content = format!("{} maze {{\n", kind);
+
+    for cell in graph.neighbours.keys() {
+        let mut attributes = vec![
+            format!("circle={}", cell.circle()),
+            format!("arc={}", cell.arc_index()),
+        ];
+        if !graph.maze.costs().is_empty() {
+            attributes.push(format!("cost={}", graph.maze.cost(cell)));
+        }
+        if graph.on_solution(cell) {
+            attributes.push(String::from("solution=true, color=red"));
+        }
+        content.push_str(&format!("  \"{}\" [{}];\n", cell_id(cell), attributes.join(", ")));
+    }
+
+    for (from, to) in graph.edges() {
+        let mut attributes = Vec::new();
+        if graph.solution_edge(from, to) {
+            attributes.push("solution=true, color=red");
+        }
+        if graph.portal_edge(from, to) {
+            attributes.push("portal=true, style=dashed");
+        }
+        let attributes = if attributes.is_empty() {
+            String::new()
+        } else {
+            format!(" [{}]", attributes.join(", "))
+        };
+        content.push_str(&format!(
+            "  \"{}\" {} \"{}\"{};\n",
+            cell_id(from),
+            connector,
+            cell_id(to),
+            attributes
+        ));
+    }
+
+    content.push_str("}\n");
+    content
+}
+
+fn write_graphml(graph: &PassageGraph) -> String {
+    let has_costs = !graph.maze.costs().is_empty();
+    let has_solution = !graph.solution.is_empty();
+    let has_portals = !graph.maze.portals().is_empty();
+
+    let mut content = String::from(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<graphml xmlns="http://graphml.graphdrawing.org/xmlns">
+  <key id="circle" for="node" attr.name="circle" attr.type="int"/>
+  <key id="arc" for="node" attr.name="arc" attr.type="int"/>
+"#,
+    );
+    if has_costs {
+        content.push_str(
+            r#"  <key id="cost" for="node" attr.name="cost" attr.type="int"><default>1</default></key>
+"#,
+        );
+    }
+    if has_solution {
+        content.push_str(
+            r#"  <key id="node_solution" for="node" attr.name="solution" attr.type="boolean"><default>false</default></key>
+  <key id="edge_solution" for="edge" attr.name="solution" attr.type="boolean"><default>false</default></key>
+"#,
+        );
+    }
+    if has_portals {
+        content.push_str(
+            r#"  <key id="portal" for="edge" attr.name="portal" attr.type="boolean"><default>false</default></key>
+"#,
+        );
+    }
+    let edge_default = if graph.directed { "directed" } else { "undirected" };
+    content.push_str(&format!("  <graph id=\"maze\" edgedefault=\"{}\">\n", edge_default));
+
+    for cell in graph.neighbours.keys() {
+        let mut data = format!(
+            r#"<data key="circle">{}</data><data key="arc">{}</data>"#,
+            cell.circle(),
+            cell.arc_index()
+        );
+        if has_costs && graph.maze.cost(cell) != 1 {
+            data.push_str(&format!(r#"<data key="cost">{}</data>"#, graph.maze.cost(cell)));
+        }
+        if graph.on_solution(cell) {
+            data.push_str(r#"<data key="node_solution">true</data>"#);
+        }
+        content.push_str(&format!("    <node id=\"{}\">{}</node>\n", cell_id(cell), data));
+    }
+
+    for (from, to) in graph.edges() {
+        let mut data = String::new();
+        if graph.solution_edge(from, to) {
+            data.push_str(r#"<data key="edge_solution">true</data>"#);
+        }
+        if graph.portal_edge(from, to) {
+            data.push_str(r#"<data key="portal">true</data>"#);
+        }
+        content.push_str(&format!(
+            "    <edge source=\"{}\" target=\"{}\">{}</edge>\n",
+            cell_id(from),
+            cell_id(to),
+            data
+        ));
+    }
+
+    content.push_str("  </graph>\n</graphml>\n");
+    content
+}
+
+// The adjacency lists name both directions of every two-way passage, so that the lists alone
+// describe the graph.
+fn graph_json(graph: &PassageGraph) -> Value {
+    let adjacency: Map<String, Value> = graph
+        .neighbours
+        .iter()
+        .map(|(cell, neighbours)| {
+            let neighbours: Vec<String> = neighbours.iter().map(cell_id).collect();
+            (cell_id(cell), Value::from(neighbours))
+        })
+        .collect();
+
+    let mut value = json!({ "directed": graph.directed, "adjacency": adjacency });
+    if !graph.solution.is_empty() {
+        let solution: Vec<String> = graph.solution.iter().map(cell_id).collect();
+        value["solution"] = Value::from(solution);
+    }
+    if !graph.maze.portals().is_empty() {
+        let portals: Vec<[String; 2]> =
+            graph.maze.portals().iter().map(|[a, b]| [cell_id(a), cell_id(b)]).collect();
+        value["portals"] = json!(portals);
+    }
+    if !graph.maze.costs().is_empty() {
+        let costs: Map<String, Value> = graph
+            .maze
+            .costs()
+            .iter()
+            .map(|(cell, cost)| (cell_id(cell), Value::from(*cost)))
+            .collect();
+        value["costs"] = Value::from(costs);
+    }
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circle_coord::RingLayout;
+    use crate::maze::factory;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn cell(circle: usize, arc_index: usize) -> CircleCoord {
+        CircleCoord::create_with_arc_index(circle, arc_index, &RingLayout::default())
+    }
+
+    #[test]
+    fn test_graph_of_a_perfect_maze_is_a_tree() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let maze = factory(6, &RingLayout::default(), &mut rng);
+        let graph = PassageGraph::new(&maze, &[]);
+
+        assert!(!graph.directed);
+        assert_eq!(graph.edges().len(), graph.neighbours.len() - 1);
+    }
+
+    #[test]
+    fn test_json_lists_the_neighbours_of_every_cell() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let maze = factory(5, &RingLayout::default(), &mut rng);
+        let route = maze.tree_diameter();
+        let value: Value = serde_json::from_str(&export_graph(&maze, GraphFormat::Json, &route))
+            .expect("The graph must be valid JSON");
+
+        let adjacency = value["adjacency"].as_object().unwrap();
+        assert_eq!(adjacency.len(), 1 + 6 + 12 + 12 + 24);
+        for neighbour in maze.accessible_neighbours(&cell(0, 0)) {
+            assert!(adjacency["0,0"].as_array().unwrap().contains(&json!(cell_id(&neighbour))));
+        }
+        assert_eq!(value["solution"].as_array().unwrap().len(), route.len());
+        assert!(value.get("portals").is_none());
+    }
+
+    #[test]
+    fn test_dot_marks_the_solution() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let maze = factory(4, &RingLayout::default(), &mut rng);
+        let route = maze.tree_diameter();
+        let dot = export_graph(&maze, GraphFormat::Dot, &route);
+
+        assert!(dot.starts_with("graph maze {\n"));
+        let marked_edges = dot.lines().filter(|l| l.contains("--") && l.contains("solution"));
+        assert_eq!(marked_edges.count(), route.len() - 1);
+        assert!(!export_graph(&maze, GraphFormat::Dot, &[]).contains("solution"));
+    }
+
+    #[test]
+    fn test_graphml_is_well_formed() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let mut maze = factory(4, &RingLayout::default(), &mut rng);
+        maze.set_cost(cell(2, 3), 5);
+        let route = maze.tree_diameter();
+        let graphml = export_graph(&maze, GraphFormat::GraphMl, &route);
+
+        let doc = roxmltree::Document::parse(&graphml).expect("GraphML must be valid XML");
+        let nodes = doc.descendants().filter(|n| n.has_tag_name("node")).count();
+        let edges = doc.descendants().filter(|n| n.has_tag_name("edge")).count();
+        assert_eq!((nodes, edges), (1 + 6 + 12 + 12, 1 + 6 + 12 + 12 - 1));
+        let costly = doc.descendants().find(|n| n.attribute("id") == Some("2,3")).unwrap();
+        let cost = costly.children().find(|n| n.attribute("key") == Some("cost")).unwrap();
+        assert_eq!(cost.text(), Some("5"));
+    }
+
+    #[test]
+    fn test_one_way_passages_make_the_graph_directed() {
+        use crate::one_way::one_way_factory;
+
+        let mut rng = StdRng::seed_from_u64(42);
+        let maze = one_way_factory(5, &RingLayout::default(), 3, &mut rng);
+        let dot = export_graph(&maze, GraphFormat::Dot, &[]);
+
+        assert!(dot.starts_with("digraph maze {\n"));
+        let (from, to) = maze.one_way().iter().next().unwrap();
+        let forward = format!("\"{}\" -> \"{}\"", cell_id(from), cell_id(to));
+        let backward = format!("\"{}\" -> \"{}\"", cell_id(to), cell_id(from));
+        assert!(dot.contains(&forward));
+        assert!(!dot.contains(&backward));
+    }
+}
@@ -1,7 +1,13 @@
+use crate::error::MazeError;
+use crate::svg::read_maze_text;
 use serde_json::Value;
 use std::fs;
 
-pub fn parse_json_file(filename: &str) -> Option<Value> {
-    let content = fs::read_to_string(filename).ok()?;
-    serde_json::from_str(&content).ok()
+/// Reads a saved maze from a JSON file or from an SVG exported with the maze embedded.
+pub fn parse_maze_file(filename: &str) -> Result<Value, MazeError> {
+    let content = fs::read_to_string(filename).map_err(|e| MazeError::Io {
+        file: String::from(filename),
+        message: e.to_string(),
+    })?;
+    read_maze_text(&content)
 }
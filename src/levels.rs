@@ -0,0 +1,188 @@
+use crate::circle_coord::{CircleCoord, RingLayout};
+use crate::error::MazeError;
+use crate::maze::{factory, Maze};
+use rand::Rng;
+use std::collections::{HashMap, VecDeque};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LevelCoord {
+    level: usize,
+    coord: CircleCoord,
+}
+
+impl LevelCoord {
+    pub fn new(level: usize, coord: CircleCoord) -> Self {
+        LevelCoord { level, coord }
+    }
+
+    pub fn level(&self) -> usize {
+        self.level
+    }
+
+    pub fn coord(&self) -> &CircleCoord {
+        &self.coord
+    }
+}
+
+/// A stack of mazes. Stair `i` connects the cell it is placed on in level `i` with the same cell
+/// in level `i + 1`.
+#[derive(Debug)]
+pub struct LayeredMaze {
+    levels: Vec<Maze>,
+    stairs: Vec<CircleCoord>,
+}
+
+impl LayeredMaze {
+    pub fn levels(&self) -> &[Maze] {
+        &self.levels
+    }
+
+    pub fn stairs(&self) -> &[CircleCoord] {
+        &self.stairs
+    }
+
+    pub fn accessible_neighbours(&self, position: &LevelCoord) -> Vec<LevelCoord> {
+        let level = position.level();
+        let mut neighbours: Vec<LevelCoord> = self.levels[level]
+            .accessible_neighbours(position.coord())
+            .into_iter()
+            .map(|coord| LevelCoord::new(level, coord))
+            .collect();
+
+        if level > 0 && &self.stairs[level - 1] == position.coord() {
+            neighbours.push(LevelCoord::new(level - 1, position.coord().clone()));
+        }
+        if level < self.stairs.len() && &self.stairs[level] == position.coord() {
+            neighbours.push(LevelCoord::new(level + 1, position.coord().clone()));
+        }
+
+        neighbours
+    }
+
+    /// Finds the route from the centre of the bottom level to the cell of the top level that is
+    /// farthest away from it. As every level is a tree and consecutive levels share exactly one
+    /// stair, the route climbs every stair in order.
+    pub fn solve(&self) -> Vec<LevelCoord> {
+        let layout = self.levels[0].layout();
+        let start = LevelCoord::new(0, CircleCoord::create_with_arc_index(0, 0, layout));
+        let top = self.levels.len() - 1;
+
+        let mut queue = VecDeque::new();
+        let mut parent: HashMap<LevelCoord, Option<LevelCoord>> = HashMap::new();
+        let mut farthest = start.clone();
+
+        queue.push_back(start.clone());
+        parent.insert(start, None);
+
+        while let Some(current) = queue.pop_front() {
+            if current.level() == top {
+                farthest = current.clone();
+            }
+
+            for neighbor in self.accessible_neighbours(&current) {
+                if !parent.contains_key(&neighbor) {
+                    parent.insert(neighbor.clone(), Some(current.clone()));
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+
+        let mut path = Vec::new();
+        let mut node = Some(farthest);
+
+        while let Some(n) = node {
+            path.push(n.clone());
+            node = parent.get(&n).and_then(|p| p.clone());
+        }
+
+        path.reverse();
+        path
+    }
+}
+
+pub fn layered_factory<R: Rng>(
+    circles: usize,
+    layout: &RingLayout,
+    levels: usize,
+    rng: &mut R,
+) -> Result<LayeredMaze, MazeError> {
+    if levels < 2 {
+        return Err(MazeError::options("a layered maze needs at least 2 levels"));
+    }
+    if circles < 2 {
+        return Err(MazeError::options("a layered maze needs at least 2 circles"));
+    }
+
+    let mazes: Vec<Maze> = (0..levels).map(|_| factory(circles, layout, rng)).collect();
+
+    let stairs = (1..levels)
+        .map(|_| {
+            let circle = rng.random_range(1..circles);
+            let arc_index = rng.random_range(0..layout.total_arcs(circle));
+            CircleCoord::create_with_arc_index(circle, arc_index, layout)
+        })
+        .collect();
+
+    Ok(LayeredMaze {
+        levels: mazes,
+        stairs,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_solve_climbs_every_stair() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let layered = layered_factory(6, &RingLayout::default(), 3, &mut rng).unwrap();
+        let path = layered.solve();
+
+        let first = path.first().unwrap();
+        assert_eq!(first.level(), 0);
+        assert_eq!(first.coord().circle(), 0);
+        assert_eq!(path.last().unwrap().level(), 2);
+
+        for step in path.windows(2) {
+            assert!(
+                layered.accessible_neighbours(&step[0]).contains(&step[1]),
+                "Path step {:?} -> {:?} is not a passage",
+                step[0],
+                step[1]
+            );
+        }
+
+        for (level, stair) in layered.stairs().iter().enumerate() {
+            assert!(path.contains(&LevelCoord::new(level, stair.clone())));
+            assert!(path.contains(&LevelCoord::new(level + 1, stair.clone())));
+        }
+    }
+
+    #[test]
+    fn test_stairs_connect_both_levels() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let layered = layered_factory(5, &RingLayout::default(), 2, &mut rng).unwrap();
+        let stair = layered.stairs()[0].clone();
+
+        let up = layered.accessible_neighbours(&LevelCoord::new(0, stair.clone()));
+        assert!(up.contains(&LevelCoord::new(1, stair.clone())));
+
+        let down = layered.accessible_neighbours(&LevelCoord::new(1, stair.clone()));
+        assert!(down.contains(&LevelCoord::new(0, stair)));
+    }
+
+    #[test]
+    fn test_layered_factory_rejects_single_level() {
+        let mut rng = StdRng::seed_from_u64(42);
+        assert!(layered_factory(5, &RingLayout::default(), 1, &mut rng).is_err());
+    }
+
+    #[test]
+    fn test_layered_factory_rejects_single_circle() {
+        let mut rng = StdRng::seed_from_u64(42);
+        assert!(layered_factory(1, &RingLayout::default(), 2, &mut rng).is_err());
+    }
+}
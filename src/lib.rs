@@ -1,13 +1,51 @@
 use wasm_bindgen::prelude::*;
-use rand::{SeedableRng, rngs::SmallRng};
+use rand::{SeedableRng, rngs::{SmallRng, StdRng}};
 
+mod branches;
 mod circle_coord;
+mod doors;
+mod dsl;
+mod error;
+mod format;
+mod graph;
+mod levels;
 mod maze;
 mod merge;
+mod metadata;
+mod one_way;
+mod portals;
+mod rotating;
+mod share;
+mod solvers;
 mod svg;
+mod terrain;
+mod validate;
+mod weave;
 
-use maze::{MazeDeserializer, MazeSerializer, factory};
-use svg::render;
+use circle_coord::RingLayout;
+use doors::door_factory;
+use dsl::{read_dsl, write_dsl};
+use error::MazeError;
+use format::MAZE_SCHEMA;
+use graph::{export_graph, GraphFormat};
+use levels::layered_factory;
+use metadata::{intended_route, Metadata};
+use maze::{
+    MazeSerializer, factory, factory_with_history, generation_events,
+    serialize_generation_event, symmetric_factory, Generation,
+};
+use one_way::one_way_factory;
+use portals::portal_factory;
+use rotating::rotating_factory;
+use share::{decode_share_code, encode_share_code};
+use solvers::{run_solver, serialize_solver_event, Solver};
+use svg::{
+    read_maze_text, render, render_branches, render_door_puzzle, render_generation,
+    render_heatmap, render_layered, render_solver_run, BranchColoring, Palette,
+};
+use terrain::{cheapest_route, terrain_factory};
+use validate::{load_valid_maze, serialize_diagnostic, validate_maze};
+use weave::weave_factory;
 
 fn create_rng() -> SmallRng {
     let mut seed = [0u8; 32];
@@ -17,27 +55,285 @@ fn create_rng() -> SmallRng {
 
 #[wasm_bindgen]
 pub fn generate_maze_svg(circles: usize) -> String {
-    let maze = factory(circles, &mut create_rng());
+    let maze = factory(circles, &RingLayout::default(), &mut create_rng());
     let path = maze.tree_diameter();
     render(&maze, &path, true)
 }
 
 #[wasm_bindgen]
-pub fn generate_maze_json(circles: usize) -> String {
-    let maze = factory(circles, &mut create_rng());
+pub fn generate_heatmap_svg(
+    circles: usize,
+    palette: &str,
+    legend: bool,
+) -> Result<String, MazeError> {
+    let palette = Palette::from_name(palette)?;
+    let maze = factory(circles, &RingLayout::default(), &mut create_rng());
+    let centre = circle_coord::CircleCoord::create_with_arc_index(0, 0, maze.layout());
+    Ok(render_heatmap(&maze, &centre, &palette, legend))
+}
+
+#[wasm_bindgen]
+pub fn generate_branches_svg(
+    circles: usize,
+    coloring: &str,
+    decisions: bool,
+) -> Result<String, MazeError> {
+    let coloring = BranchColoring::from_name(coloring)?;
+    let maze = factory(circles, &RingLayout::default(), &mut create_rng());
+    let path = maze.tree_diameter();
+    Ok(render_branches(&maze, &path, &coloring, decisions))
+}
+
+#[wasm_bindgen]
+pub fn generate_maze_animation_svg(circles: usize, duration: f64) -> String {
+    let (maze, order) = factory_with_history(circles, &RingLayout::default(), &mut create_rng());
+    render_generation(&maze, &order, duration)
+}
+
+/// Drives the generator one event at a time, so the page can draw each step at its own pace.
+#[wasm_bindgen]
+pub struct GenerationStream {
+    generation: Generation<SmallRng>,
+}
+
+#[wasm_bindgen]
+impl GenerationStream {
+    #[wasm_bindgen(constructor)]
+    pub fn new(circles: usize) -> GenerationStream {
+        GenerationStream {
+            generation: generation_events(circles, &RingLayout::default(), create_rng()),
+        }
+    }
+
+    /// The next event as a JSON string, or `None` once the maze is complete.
+    pub fn next_event(&mut self) -> Option<String> {
+        self.generation.next().map(|event| serialize_generation_event(&event).to_string())
+    }
+
+    /// Runs the remaining steps and renders the finished maze.
+    pub fn finish_svg(self) -> String {
+        let (maze, _) = self.generation.finish();
+        let path = maze.tree_diameter();
+        render(&maze, &path, true)
+    }
+}
+
+#[wasm_bindgen]
+pub fn generate_symmetric_maze_svg(circles: usize, folds: usize) -> Result<String, MazeError> {
+    let maze = symmetric_factory(circles, &RingLayout::default(), folds, &mut create_rng())?;
+    let path = maze.tree_diameter();
+    Ok(render(&maze, &path, true))
+}
+
+#[wasm_bindgen]
+pub fn generate_layered_maze_svg(circles: usize, levels: usize) -> Result<String, MazeError> {
+    let layered = layered_factory(circles, &RingLayout::default(), levels, &mut create_rng())?;
+    let path = layered.solve();
+    Ok(render_layered(&layered, &path, true))
+}
+
+#[wasm_bindgen]
+pub fn generate_weave_maze_svg(circles: usize, max_crossings: usize) -> String {
+    let maze = weave_factory(circles, &RingLayout::default(), max_crossings, &mut create_rng());
+    let path = maze.tree_diameter();
+    render(&maze, &path, true)
+}
+
+#[wasm_bindgen]
+pub fn generate_one_way_maze_svg(circles: usize, count: usize) -> String {
+    let maze = one_way_factory(circles, &RingLayout::default(), count, &mut create_rng());
+    let path = maze.tree_diameter();
+    render(&maze, &path, true)
+}
+
+#[wasm_bindgen]
+pub fn generate_portal_maze_svg(circles: usize, pairs: usize) -> Result<String, MazeError> {
+    let maze = portal_factory(circles, &RingLayout::default(), pairs, &mut create_rng())?;
+    let path = maze.tree_diameter();
+    Ok(render(&maze, &path, true))
+}
+
+#[wasm_bindgen]
+pub fn generate_terrain_maze_svg(circles: usize, patches: usize) -> String {
+    let maze = terrain_factory(circles, &RingLayout::default(), patches, &mut create_rng());
+    let path = cheapest_route(&maze);
+    render(&maze, &path, true)
+}
+
+#[wasm_bindgen]
+pub fn generate_rotating_puzzle_svg(circles: usize, solved: bool) -> Result<String, MazeError> {
+    let puzzle = rotating_factory(circles, &RingLayout::default(), &mut create_rng())?;
+    if !solved {
+        return Ok(render(puzzle.maze(), &[puzzle.entrance().clone(), puzzle.centre()], false));
+    }
+
+    let offsets =
+        puzzle.solve().ok_or_else(|| MazeError::options("Generated puzzle has no solution"))?;
+    let path =
+        puzzle.route(&offsets).ok_or_else(|| MazeError::options("Solution does not open a route"))?;
+    Ok(render(&puzzle.maze().rotate_rings(&offsets), &path, true))
+}
+
+#[wasm_bindgen]
+pub fn generate_door_puzzle_svg(circles: usize, doors: usize) -> Result<String, MazeError> {
+    let puzzle = door_factory(circles, &RingLayout::default(), doors, &mut create_rng())?;
+    let path =
+        puzzle.solve().ok_or_else(|| MazeError::options("Generated puzzle has no solution"))?;
+    Ok(render_door_puzzle(&puzzle, &path, true))
+}
+
+/// Generates a maze and saves it with its seed, creation time and solution.
+#[wasm_bindgen]
+pub fn generate_maze_json(circles: usize, title: Option<String>) -> String {
+    let mut seed = [0u8; 8];
+    getrandom::fill(&mut seed).expect("Failed to get random seed");
+    let seed = u64::from_le_bytes(seed);
+    let mut maze = factory(circles, &RingLayout::default(), &mut StdRng::seed_from_u64(seed));
+
+    let now = (js_sys::Date::now() / 1000.0) as u64;
+    let mut metadata = Metadata::generated("standard", seed, now);
+    metadata.title = title;
+    metadata.set_solution(&intended_route(&maze));
+    maze.set_metadata(metadata);
+
     let serialized = MazeSerializer::serialize(&maze);
     serde_json::to_string_pretty(&serialized)
         .unwrap_or_else(|_| String::from("{}"))
 }
 
+/// The JSON Schema that files from `generate_maze_json` follow.
 #[wasm_bindgen]
-pub fn load_maze_svg(json_string: &str) -> Result<String, String> {
-    let json_value: serde_json::Value = serde_json::from_str(json_string)
-        .map_err(|e| format!("Failed to parse JSON: {}", e))?;
+pub fn maze_json_schema() -> String {
+    String::from(MAZE_SCHEMA)
+}
+
+/// Errors reach JavaScript as `Error` objects whose `name` tells the kind of problem
+/// (`JsonSyntaxError`, `SchemaError`, `SemanticError`, `ShareCodeError`, `SvgError`, `DslError`
+/// or `OptionsError`) and whose `path` property, when set, points to the offending value of the
+/// uploaded file.
+impl From<MazeError> for JsValue {
+    fn from(error: MazeError) -> JsValue {
+        let js_error = js_sys::Error::new(&error.to_string());
+        js_error.set_name(error.name());
+        if let Some(path) = error.path() {
+            let _ = js_sys::Reflect::set(&js_error, &JsValue::from_str("path"), &path.into());
+        }
+        js_error.into()
+    }
+}
 
-    let maze = MazeDeserializer::deserialize(json_value)
-        .map_err(|e| format!("Failed to deserialize maze: {}", e))?;
+// Uploaded mazes are checked first, since broken ones would render as garbage. Besides JSON,
+// every function that takes an uploaded maze accepts an SVG exported with the maze embedded.
+fn parse_maze(json_string: &str) -> Result<maze::Maze, MazeError> {
+    load_valid_maze(read_maze_text(json_string)?)
+}
 
-    let path = maze.tree_diameter();
+/// Recovers the saved maze from an exported SVG, for example to offer it as a download.
+#[wasm_bindgen]
+pub fn maze_json_from_svg(svg_string: &str) -> Result<String, MazeError> {
+    let maze = parse_maze(svg_string)?;
+    let serialized = MazeSerializer::serialize(&maze);
+    Ok(serde_json::to_string_pretty(&serialized).unwrap_or_else(|_| String::from("{}")))
+}
+
+/// Checks an uploaded maze and lists its problems as JSON objects with `severity`, `kind`,
+/// `path` and `message`. An empty list means the maze is valid.
+#[wasm_bindgen]
+pub fn validate_maze_json(json_string: &str) -> Result<String, MazeError> {
+    let diagnostics = validate_maze(read_maze_text(json_string)?)?;
+    let diagnostics: Vec<serde_json::Value> =
+        diagnostics.iter().map(serialize_diagnostic).collect();
+    Ok(serde_json::Value::from(diagnostics).to_string())
+}
+
+#[wasm_bindgen]
+pub fn load_maze_svg(json_string: &str) -> Result<String, MazeError> {
+    let maze = parse_maze(json_string)?;
+    let path = intended_route(&maze);
+    Ok(render(&maze, &path, true))
+}
+
+/// Animates a solver walking from the start to the goal of the given maze. Mazes without a
+/// saved start and goal use the ends of their longest path.
+#[wasm_bindgen]
+pub fn solve_maze_animation_svg(
+    json_string: &str,
+    solver: &str,
+    duration: f64,
+) -> Result<String, MazeError> {
+    let solver = Solver::from_name(solver)?;
+    let maze = parse_maze(json_string)?;
+    let route = intended_route(&maze);
+    let (start, goal) = (&route[0], &route[route.len() - 1]);
+    let run = run_solver(&maze, solver, start, goal);
+    Ok(render_solver_run(&maze, &run, start, goal, duration))
+}
+
+/// The steps of a solver on the given maze as JSON, with the route it found or `null`.
+#[wasm_bindgen]
+pub fn solve_maze_events(json_string: &str, solver: &str) -> Result<String, MazeError> {
+    let solver = Solver::from_name(solver)?;
+    let maze = parse_maze(json_string)?;
+    let route = intended_route(&maze);
+    let run = run_solver(&maze, solver, &route[0], &route[route.len() - 1]);
+
+    let events: Vec<serde_json::Value> =
+        run.events().iter().map(serialize_solver_event).collect();
+    let path = run.path().map(|path| {
+        path.iter()
+            .map(|coord| serde_json::json!({ "circle": coord.circle(), "arc": coord.arc_index() }))
+            .collect::<Vec<_>>()
+    });
+    Ok(serde_json::json!({ "events": events, "path": path }).to_string())
+}
+
+/// The passage graph of an uploaded maze as `dot`, `graphml` or `json`, with the intended route
+/// marked if `mark_solution` is set.
+#[wasm_bindgen]
+pub fn maze_graph(
+    json_string: &str,
+    format: &str,
+    mark_solution: bool,
+) -> Result<String, MazeError> {
+    let format = GraphFormat::from_name(format)?;
+    let maze = parse_maze(json_string)?;
+    let route = if mark_solution { intended_route(&maze) } else { Vec::new() };
+    Ok(export_graph(&maze, format, &route))
+}
+
+/// A short, URL-safe code for an uploaded maze that only has walls.
+#[wasm_bindgen]
+pub fn maze_share_code(json_string: &str) -> Result<String, MazeError> {
+    let maze = parse_maze(json_string)?;
+    encode_share_code(&maze)
+}
+
+/// Turns a share code back into a saved maze, for example to offer it as a download.
+#[wasm_bindgen]
+pub fn share_code_to_maze_json(code: &str) -> Result<String, MazeError> {
+    let maze = decode_share_code(code)?;
+    let serialized = MazeSerializer::serialize(&maze);
+    Ok(serde_json::to_string_pretty(&serialized).unwrap_or_else(|_| String::from("{}")))
+}
+
+/// The walls of an uploaded maze as readable text, ring by ring.
+#[wasm_bindgen]
+pub fn maze_dsl(json_string: &str) -> Result<String, MazeError> {
+    let maze = parse_maze(json_string)?;
+    write_dsl(&maze)
+}
+
+/// Turns maze text back into a saved maze.
+#[wasm_bindgen]
+pub fn dsl_to_maze_json(text: &str) -> Result<String, MazeError> {
+    let maze = read_dsl(text)?;
+    let serialized = MazeSerializer::serialize(&maze);
+    Ok(serde_json::to_string_pretty(&serialized).unwrap_or_else(|_| String::from("{}")))
+}
+
+#[wasm_bindgen]
+pub fn load_share_code_svg(code: &str) -> Result<String, MazeError> {
+    let maze = decode_share_code(code)?;
+    let path = intended_route(&maze);
     Ok(render(&maze, &path, true))
 }
@@ -1,28 +1,152 @@
 use crate::{
-    json::parse_json_file,
-    maze::{MazeDeserializer, MazeSerializer, factory},
-    svg::render,
-    circle_coord::CircleCoord,
+    branches::find_branches,
+    doors::door_factory,
+    dsl::{read_dsl, write_dsl},
+    format::MAZE_SCHEMA,
+    graph::{export_graph, GraphFormat},
+    json::parse_maze_file,
+    levels::layered_factory,
+    metadata::{intended_route, Metadata},
+    maze::{
+        MazeSerializer, factory, factory_with_history, generation_events,
+        serialize_generation_event, symmetric_factory,
+    },
+    one_way::one_way_factory,
+    portals::portal_factory,
+    rotating::rotating_factory,
+    share::{decode_share_code, encode_share_code},
+    solvers::{run_solver, serialize_solver_event, Solver},
+    terrain::terrain_factory,
+    validate::{load_valid_maze, serialize_diagnostic, validate_maze},
+    svg::{
+        render, render_branches, render_door_puzzle, render_generation, render_heatmap,
+        render_layered, render_solver_run, BranchColoring, Palette,
+    },
+    weave::weave_factory,
+    circle_coord::{CircleCoord, RingLayout, Subdivision, DEFAULT_FIRST_RING_ARCS},
+    error::{MazeError, Severity},
 };
 use clap::Parser;
+use rand::{rngs::StdRng, SeedableRng};
 use std::fs::File;
 use std::io::Write;
+use std::time::{SystemTime, UNIX_EPOCH};
 
+mod branches;
 mod circle_coord;
+mod doors;
+mod dsl;
+mod error;
+mod format;
+mod graph;
 mod json;
+mod levels;
 mod maze;
 mod merge;
+mod metadata;
+mod one_way;
+mod portals;
+mod rotating;
+mod share;
+mod solvers;
+mod terrain;
+mod validate;
 mod svg;
+mod weave;
+
+fn write_svg(svg_content: &str) -> std::io::Result<()> {
+    let mut file = File::create("maze.svg")?;
+    file.write_all(svg_content.as_bytes())?;
+    Ok(())
+}
 
 fn render_to_file(
     maze: &crate::maze::Maze,
     path: &[CircleCoord],
     include_path: bool
 ) -> std::io::Result<()> {
-    let svg_content = render(maze, path, include_path);
-    let mut file = File::create("maze.svg")?;
-    file.write_all(svg_content.as_bytes())?;
-    Ok(())
+    write_svg(&render(maze, path, include_path))
+}
+
+fn parse_cell(text: &str, maze: &crate::maze::Maze) -> Result<CircleCoord, String> {
+    let (circle, arc) = text
+        .split_once(',')
+        .ok_or(format!("Cell '{}' must be given as <circle>,<arc>", text))?;
+    let circle: usize = circle.trim().parse().map_err(|_| format!("Invalid circle in '{}'", text))?;
+    let arc: usize = arc.trim().parse().map_err(|_| format!("Invalid arc in '{}'", text))?;
+
+    if circle >= maze.circles() || arc >= maze.layout().total_arcs(circle) {
+        return Err(format!("Cell '{}' is outside the maze", text));
+    }
+    Ok(CircleCoord::create_with_arc_index(circle, arc, maze.layout()))
+}
+
+// Animations are written with their length in seconds, which has to be a positive number.
+fn parse_duration(option: &str, seconds: f64) -> Result<f64, String> {
+    if !seconds.is_finite() || seconds <= 0.0 {
+        return Err(format!("{} must be a positive number of seconds, not {}", option, seconds));
+    }
+    Ok(seconds)
+}
+
+// Each generator mode makes a different kind of maze, so only one can be chosen, and only when
+// creating a maze. The puzzles and the animation draw their own maze.svg and write no maze.json,
+// so none of the options that work on the finished maze apply to them.
+fn check_modes(cli: &Cli) -> Result<(), String> {
+    let modes = [
+        ("--symmetry", cli.symmetry.is_some()),
+        ("--levels", cli.levels.is_some()),
+        ("--weave", cli.weave.is_some()),
+        ("--doors", cli.doors.is_some()),
+        ("--one-way", cli.one_way.is_some()),
+        ("--rotating", cli.rotating),
+        ("--portals", cli.portals.is_some()),
+        ("--terrain", cli.terrain.is_some()),
+        ("--animate", cli.animate.is_some()),
+        ("--events", cli.events),
+    ];
+    let chosen: Vec<&str> = modes.iter().filter(|(_, set)| *set).map(|(name, _)| *name).collect();
+    let mode = match chosen[..] {
+        [] => return Ok(()),
+        [mode] if cli.create.is_none() => {
+            return Err(format!("{} only works with --create", mode));
+        }
+        [mode] => mode,
+        _ => return Err(format!("{} cannot be combined; choose one", chosen.join(" and "))),
+    };
+    if !matches!(mode, "--levels" | "--doors" | "--rotating" | "--animate") {
+        return Ok(());
+    }
+
+    let options = [
+        ("--share", cli.share),
+        ("--dsl", cli.dsl),
+        ("--heatmap", cli.heatmap.is_some()),
+        ("--graph", cli.graph.is_some()),
+        ("--solve", cli.solve.is_some()),
+        ("--branches", cli.branches.is_some()),
+        ("--title", cli.title.is_some()),
+        ("--author", cli.author.is_some()),
+    ];
+    match options.iter().find(|(_, set)| *set) {
+        Some((option, _)) => Err(format!(
+            "{} draws its own maze.svg and writes no maze.json, so it cannot be combined with {}",
+            mode, option
+        )),
+        None => Ok(()),
+    }
+}
+
+fn exit_with_error(message: &str) -> ! {
+    eprintln!("Error: {}", message);
+    std::process::exit(1);
+}
+
+fn exit_with_maze_error(error: MazeError) -> ! {
+    match error.path().filter(|path| !path.is_empty()) {
+        Some(path) => exit_with_error(&format!("{} at {}: {}", error.name(), path, error)),
+        None => exit_with_error(&format!("{}: {}", error.name(), error)),
+    }
 }
 
 #[derive(Parser)]
@@ -37,13 +161,221 @@ struct Cli {
 
     #[arg(long)]
     no_path: bool,
+
+    #[arg(long, default_value_t = DEFAULT_FIRST_RING_ARCS)]
+    first_ring_arcs: usize,
+
+    #[arg(long)]
+    split_ratio: Option<f64>,
+
+    #[arg(long)]
+    symmetry: Option<usize>,
+
+    /// Draw this many stacked mazes joined by stairs. Writes no maze.json
+    #[arg(long)]
+    levels: Option<usize>,
+
+    #[arg(long)]
+    weave: Option<usize>,
+
+    /// Draw a puzzle with up to this many locked doors. Writes no maze.json
+    #[arg(long)]
+    doors: Option<usize>,
+
+    #[arg(long)]
+    one_way: Option<usize>,
+
+    /// Draw a rotating-ring puzzle. Writes no maze.json
+    #[arg(long)]
+    rotating: bool,
+
+    #[arg(long)]
+    portals: Option<usize>,
+
+    #[arg(long)]
+    terrain: Option<usize>,
+
+    #[arg(long)]
+    heatmap: Option<String>,
+
+    #[arg(long)]
+    heatmap_from: Option<String>,
+
+    #[arg(long)]
+    legend: bool,
+
+    #[arg(long)]
+    branches: Option<String>,
+
+    #[arg(long)]
+    decisions: bool,
+
+    /// Animate the generator over this many seconds. Writes no maze.json
+    #[arg(long)]
+    animate: Option<f64>,
+
+    #[arg(long)]
+    events: bool,
+
+    #[arg(long)]
+    solve: Option<String>,
+
+    #[arg(long, default_value_t = 10.0)]
+    solve_duration: f64,
+
+    #[arg(long)]
+    solve_events: bool,
+
+    #[arg(long)]
+    upgrade: bool,
+
+    #[arg(long)]
+    schema: bool,
+
+    #[arg(long)]
+    validate: Option<String>,
+
+    #[arg(long)]
+    share: bool,
+
+    #[arg(long)]
+    from_code: Option<String>,
+
+    #[arg(long)]
+    seed: Option<u64>,
+
+    #[arg(long)]
+    title: Option<String>,
+
+    #[arg(long)]
+    author: Option<String>,
+
+    #[arg(long)]
+    graph: Option<String>,
+
+    #[arg(long)]
+    dsl: bool,
+
+    #[arg(long)]
+    from_dsl: Option<String>,
 }
 
 fn main() {
     let cli = Cli::parse();
 
+    if cli.schema {
+        print!("{}", MAZE_SCHEMA);
+        return;
+    }
+
+    if let Some(path) = cli.validate {
+        let json_value = parse_maze_file(&path).unwrap_or_else(|e| exit_with_maze_error(e));
+        let diagnostics = validate_maze(json_value).unwrap_or_else(|e| exit_with_maze_error(e));
+        for diagnostic in &diagnostics {
+            println!("{}", serialize_diagnostic(diagnostic));
+        }
+        let errors = diagnostics.iter().filter(|d| d.severity() == Severity::Error).count();
+        eprintln!("{} errors, {} warnings", errors, diagnostics.len() - errors);
+        if errors > 0 {
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    check_modes(&cli).unwrap_or_else(|e| exit_with_error(&e));
+
     let maze = if let Some(circles) = cli.create {
-        let maze = factory(circles, &mut rand::rng());
+        let subdivision = match cli.split_ratio {
+            Some(ratio) => Subdivision::WidthRatio(ratio),
+            None => Subdivision::PowerOfTwo,
+        };
+        let layout = RingLayout::new(cli.first_ring_arcs, subdivision)
+            .unwrap_or_else(|e| exit_with_maze_error(e));
+        let seed = cli.seed.unwrap_or_else(rand::random);
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        if let Some(levels) = cli.levels {
+            let layered = layered_factory(circles, &layout, levels, &mut rng)
+                .unwrap_or_else(|e| exit_with_maze_error(e));
+            let path = layered.solve();
+            write_svg(&render_layered(&layered, &path, !cli.no_path))
+                .expect("Failed to render SVG");
+            return;
+        }
+
+        if let Some(doors) = cli.doors {
+            let puzzle = door_factory(circles, &layout, doors, &mut rng)
+                .unwrap_or_else(|e| exit_with_maze_error(e));
+            let path = puzzle.solve().expect("Generated puzzle has no solution");
+            write_svg(&render_door_puzzle(&puzzle, &path, !cli.no_path))
+                .expect("Failed to render SVG");
+            return;
+        }
+
+        if cli.rotating {
+            let puzzle = rotating_factory(circles, &layout, &mut rng)
+                .unwrap_or_else(|e| exit_with_maze_error(e));
+            let markers = [puzzle.entrance().clone(), puzzle.centre()];
+            write_svg(&render(puzzle.maze(), &markers, false)).expect("Failed to render SVG");
+            if !cli.no_path {
+                let offsets = puzzle.solve().expect("Generated puzzle has no solution");
+                for (circle, offset) in offsets.iter().enumerate().skip(1) {
+                    println!("Ring {}: turn {} arcs clockwise", circle, offset);
+                }
+                let route = puzzle.route(&offsets).expect("Solution does not open a route");
+                println!("Route to the centre: {} cells", route.len());
+            }
+            return;
+        }
+
+        if let Some(duration) = cli.animate {
+            let duration = parse_duration("--animate", duration)
+                .unwrap_or_else(|e| exit_with_error(&e));
+            let (maze, order) = factory_with_history(circles, &layout, &mut rng);
+            write_svg(&render_generation(&maze, &order, duration)).expect("Failed to render SVG");
+            return;
+        }
+
+        let modes = [cli.symmetry, cli.weave, cli.one_way, cli.portals, cli.terrain];
+        let algorithm = match modes {
+            [Some(_), _, _, _, _] => "symmetric",
+            [_, Some(_), _, _, _] => "weave",
+            [_, _, Some(_), _, _] => "one_way",
+            [_, _, _, Some(_), _] => "portals",
+            [_, _, _, _, Some(_)] => "terrain",
+            [None, None, None, None, None] => "standard",
+        };
+
+        let mut maze = match modes {
+            [Some(folds), _, _, _, _] => {
+                symmetric_factory(circles, &layout, folds, &mut rng)
+                    .unwrap_or_else(|e| exit_with_maze_error(e))
+            }
+            [_, Some(max_crossings), _, _, _] => {
+                weave_factory(circles, &layout, max_crossings, &mut rng)
+            }
+            [_, _, Some(count), _, _] => one_way_factory(circles, &layout, count, &mut rng),
+            [_, _, _, Some(pairs), _] => portal_factory(circles, &layout, pairs, &mut rng)
+                .unwrap_or_else(|e| exit_with_maze_error(e)),
+            [_, _, _, _, Some(patches)] => {
+                terrain_factory(circles, &layout, patches, &mut rng)
+            }
+            [None, None, None, None, None] if cli.events => {
+                let mut generation = generation_events(circles, &layout, rng);
+                for event in generation.by_ref() {
+                    println!("{}", serialize_generation_event(&event));
+                }
+                generation.finish().0
+            }
+            [None, None, None, None, None] => factory(circles, &layout, &mut rng),
+        };
+
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+        let mut metadata = Metadata::generated(algorithm, seed, now);
+        metadata.title = cli.title;
+        metadata.author = cli.author;
+        metadata.set_solution(&intended_route(&maze));
+        maze.set_metadata(metadata);
 
         let serialized = MazeSerializer::serialize(&maze);
         let json_string = serde_json::to_string_pretty(&serialized)
@@ -53,13 +385,103 @@ fn main() {
 
         maze
     } else if let Some(path) = cli.parse {
-        let json_value = parse_json_file(&path).expect("Failed to parse JSON file");
-        MazeDeserializer::deserialize(json_value).expect("Failed to deserialize maze")
+        // Every mode below renders to maze.svg, which must not be the maze being read.
+        let output = std::fs::canonicalize("maze.svg").ok();
+        if output.is_some() && std::fs::canonicalize(&path).ok() == output {
+            exit_with_error("Cannot --parse maze.svg, since it would be overwritten; rename it");
+        }
+        let json_value = parse_maze_file(&path).unwrap_or_else(|e| exit_with_maze_error(e));
+        let maze = load_valid_maze(json_value).unwrap_or_else(|e| exit_with_maze_error(e));
+        // A maze recovered from an SVG is upgraded into a JSON file beside it, never over it.
+        if cli.upgrade {
+            let target = if path.to_lowercase().ends_with(".svg") {
+                std::path::Path::new(&path).with_extension("json")
+            } else {
+                std::path::PathBuf::from(&path)
+            };
+            let json_string = serde_json::to_string_pretty(&MazeSerializer::serialize(&maze))
+                .expect("Failed to serialize maze to JSON string");
+            std::fs::write(target, json_string).expect("Failed to write the maze");
+        }
+        maze
+    } else if let Some(code) = cli.from_code {
+        decode_share_code(&code).unwrap_or_else(|e| exit_with_maze_error(e))
+    } else if let Some(path) = cli.from_dsl {
+        let text = std::fs::read_to_string(&path).unwrap_or_else(|e| {
+            exit_with_maze_error(MazeError::Io { file: path.clone(), message: e.to_string() })
+        });
+        read_dsl(&text).unwrap_or_else(|e| exit_with_maze_error(e))
     } else {
-        eprintln!("Error: Either --parse or --create must be provided");
-        std::process::exit(1);
+        exit_with_error("Either --parse, --create, --from-code or --from-dsl must be provided");
     };
 
-    let path = maze.tree_diameter();
+    if cli.share {
+        let code = encode_share_code(&maze).unwrap_or_else(|e| exit_with_maze_error(e));
+        println!("{}", code);
+    }
+
+    if cli.dsl {
+        let text = write_dsl(&maze).unwrap_or_else(|e| exit_with_maze_error(e));
+        print!("{}", text);
+    }
+
+    if let Some(palette) = cli.heatmap {
+        let palette = Palette::from_name(&palette).unwrap_or_else(|e| exit_with_maze_error(e));
+        let start = match cli.heatmap_from {
+            Some(cell) => parse_cell(&cell, &maze).unwrap_or_else(|e| exit_with_error(&e)),
+            None => CircleCoord::create_with_arc_index(0, 0, maze.layout()),
+        };
+        write_svg(&render_heatmap(&maze, &start, &palette, cli.legend))
+            .expect("Failed to render SVG");
+        return;
+    }
+
+    let path = intended_route(&maze);
+
+    if let Some(format) = cli.graph {
+        let format = GraphFormat::from_name(&format).unwrap_or_else(|e| exit_with_maze_error(e));
+        let solution = if cli.no_path { &[][..] } else { &path[..] };
+        // The JSON graph is kept apart from maze.json, which holds the maze itself.
+        let filename = match format {
+            GraphFormat::Dot => "maze.dot",
+            GraphFormat::GraphMl => "maze.graphml",
+            GraphFormat::Json => "maze.graph.json",
+        };
+        std::fs::write(filename, export_graph(&maze, format, solution))
+            .expect("Failed to write the graph");
+    }
+
+    if let Some(solver) = cli.solve {
+        let solver = Solver::from_name(&solver).unwrap_or_else(|e| exit_with_maze_error(e));
+        let duration = parse_duration("--solve-duration", cli.solve_duration)
+            .unwrap_or_else(|e| exit_with_error(&e));
+        let (start, goal) = (&path[0], &path[path.len() - 1]);
+        let run = run_solver(&maze, solver, start, goal);
+        write_svg(&render_solver_run(&maze, &run, start, goal, duration))
+            .expect("Failed to render SVG");
+        if cli.solve_events {
+            for event in run.events() {
+                println!("{}", serialize_solver_event(event));
+            }
+        }
+        match run.path() {
+            Some(route) => println!("Steps: {}, route: {} cells", run.events().len(), route.len()),
+            None => println!("Steps: {}, the goal was not reached", run.events().len()),
+        }
+        return;
+    }
+
+    if let Some(coloring) = cli.branches {
+        let coloring =
+            BranchColoring::from_name(&coloring).unwrap_or_else(|e| exit_with_maze_error(e));
+        write_svg(&render_branches(&maze, &path, &coloring, cli.decisions))
+            .expect("Failed to render SVG");
+        let branches = find_branches(&maze, &path);
+        println!("Dead-end branches: {}", branches.len());
+        if let Some(deepest) = branches.iter().map(|branch| branch.depth()).max() {
+            println!("Deepest branch: {} cells", deepest);
+        }
+        return;
+    }
     render_to_file(&maze, &path, !cli.no_path).expect("Failed to render SVG");
 }
@@ -1,13 +1,62 @@
-use crate::circle_coord::{calc_total_arcs, CircleCoord};
+use crate::circle_coord::{CircleCoord, RingLayout};
+use crate::error::{Diagnostic, DiagnosticKind, MazeError};
+use crate::format::{
+    migrate, CellFile, CostFile, CrossingFile, LayoutFile, MazeFile, MetadataFile, OneWayFile,
+    FORMAT_VERSION,
+};
+use crate::metadata::Metadata;
 use rand::{seq::SliceRandom, Rng};
-use serde_json::Value;
-use std::collections::HashSet;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+/// A wall between two adjacent cells: either an arc on the inner side of a cell or a line on its
+/// counter-clockwise side.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Wall {
+    Arc(CircleCoord),
+    Line(CircleCoord),
+}
+
+impl Wall {
+    /// Returns the wall separating two cells, or `None` if the cells are not adjacent.
+    pub fn between(a: &CircleCoord, b: &CircleCoord) -> Option<Wall> {
+        if a.circle() == b.circle() && a.circle() > 0 {
+            if b == &a.next_clockwise() {
+                return Some(Wall::Line(b.clone()));
+            }
+            if b == &a.next_counter_clockwise() {
+                return Some(Wall::Line(a.clone()));
+            }
+        } else if b.circle() == a.circle() + 1 && &b.next_in() == a {
+            return Some(Wall::Arc(b.clone()));
+        } else if a.circle() == b.circle() + 1 && &a.next_in() == b {
+            return Some(Wall::Arc(a.clone()));
+        }
+        None
+    }
+}
+
+/// The direction of the passage that tunnels under a crossing cell. The passage over the
+/// crossing runs in the other direction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TunnelAxis {
+    Radial,
+    Ring,
+}
 
 #[derive(Debug)]
 pub struct Maze {
     circles: usize,
+    layout: RingLayout,
     arcs: HashSet<CircleCoord>,
     lines: HashSet<CircleCoord>,
+    crossings: HashMap<CircleCoord, TunnelAxis>,
+    one_way: HashSet<(CircleCoord, CircleCoord)>,
+    portals: Vec<[CircleCoord; 2]>,
+    costs: HashMap<CircleCoord, u32>,
+    metadata: Metadata,
 }
 
 impl Maze {
@@ -15,6 +64,10 @@ impl Maze {
         self.circles
     }
 
+    pub fn layout(&self) -> &RingLayout {
+        &self.layout
+    }
+
     pub fn arcs(&self) -> &HashSet<CircleCoord> {
         &self.arcs
     }
@@ -23,7 +76,152 @@ impl Maze {
         &self.lines
     }
 
+    pub fn crossings(&self) -> &HashMap<CircleCoord, TunnelAxis> {
+        &self.crossings
+    }
+
+    /// The one-way passages as `(from, to)` pairs. Such a passage can only be walked from `from`
+    /// to `to`.
+    pub fn one_way(&self) -> &HashSet<(CircleCoord, CircleCoord)> {
+        &self.one_way
+    }
+
+    /// Pairs of teleporter cells. Stepping onto either cell of a pair leads to the other one.
+    pub fn portals(&self) -> &[[CircleCoord; 2]] {
+        &self.portals
+    }
+
+    /// The cells that are more expensive to enter than the default cost of 1.
+    pub fn costs(&self) -> &HashMap<CircleCoord, u32> {
+        &self.costs
+    }
+
+    pub fn metadata(&self) -> &Metadata {
+        &self.metadata
+    }
+
+    pub fn set_metadata(&mut self, metadata: Metadata) {
+        self.metadata = metadata;
+    }
+
+    /// Whether the maze is nothing but walls, without crossings, one-way passages, portals or
+    /// costs. Share codes and maze text hold only walls.
+    pub fn has_only_walls(&self) -> bool {
+        self.crossings.is_empty()
+            && self.one_way.is_empty()
+            && self.portals.is_empty()
+            && self.costs.is_empty()
+    }
+
+    pub fn cost(&self, coord: &CircleCoord) -> u32 {
+        self.costs.get(coord).copied().unwrap_or(1)
+    }
+
+    pub fn set_cost(&mut self, coord: CircleCoord, cost: u32) {
+        if cost == 1 {
+            self.costs.remove(&coord);
+        } else {
+            self.costs.insert(coord, cost);
+        }
+    }
+
+    pub fn add_wall(&mut self, wall: Wall) {
+        match wall {
+            Wall::Arc(coord) => self.arcs.insert(coord),
+            Wall::Line(coord) => self.lines.insert(coord),
+        };
+    }
+
+    pub fn remove_wall(&mut self, wall: &Wall) {
+        match wall {
+            Wall::Arc(coord) => self.arcs.remove(coord),
+            Wall::Line(coord) => self.lines.remove(coord),
+        };
+    }
+
+    /// Returns the maze with ring `c` turned clockwise by `offsets[c]` arcs. A ring carries its
+    /// lines and the arcs on its inner side; rings without an offset and the outer boundary stay
+    /// in place.
+    pub fn rotate_rings(&self, offsets: &[usize]) -> Maze {
+        let rotate = |coord: &CircleCoord| {
+            let circle = coord.circle();
+            match offsets.get(circle) {
+                Some(offset) if circle > 0 => {
+                    let total_arcs = self.layout.total_arcs(circle);
+                    let arc_index = (coord.arc_index() + offset) % total_arcs;
+                    CircleCoord::create_with_arc_index(circle, arc_index, &self.layout)
+                }
+                _ => coord.clone(),
+            }
+        };
+
+        Maze {
+            circles: self.circles,
+            layout: self.layout,
+            arcs: self.arcs.iter().map(rotate).collect(),
+            lines: self.lines.iter().map(rotate).collect(),
+            crossings: self.crossings.iter().map(|(c, axis)| (rotate(c), *axis)).collect(),
+            one_way: self.one_way.iter().map(|(from, to)| (rotate(from), rotate(to))).collect(),
+            portals: self.portals.iter().map(|[a, b]| [rotate(a), rotate(b)]).collect(),
+            costs: self.costs.iter().map(|(c, cost)| (rotate(c), *cost)).collect(),
+            metadata: Metadata::default(),
+        }
+    }
+
+    pub fn add_crossing(&mut self, coord: CircleCoord, axis: TunnelAxis) {
+        self.crossings.insert(coord, axis);
+    }
+
+    pub fn add_one_way(&mut self, from: CircleCoord, to: CircleCoord) {
+        self.one_way.insert((from, to));
+    }
+
+    pub fn add_portal(&mut self, a: CircleCoord, b: CircleCoord) {
+        self.portals.push([a, b]);
+    }
+
+    /// The arcs a radial tunnel passes under. They are walls of the passage over the crossing and
+    /// are drawn as part of the crossing instead of as regular borders.
+    pub fn tunnel_arcs(&self) -> HashSet<CircleCoord> {
+        self.crossings
+            .iter()
+            .filter(|(_, axis)| **axis == TunnelAxis::Radial)
+            .flat_map(|(coord, _)| [coord.clone(), coord.next_out()])
+            .collect()
+    }
+
+    /// The lines a ring tunnel passes under.
+    pub fn tunnel_lines(&self) -> HashSet<CircleCoord> {
+        self.crossings
+            .iter()
+            .filter(|(_, axis)| **axis == TunnelAxis::Ring)
+            .flat_map(|(coord, _)| [coord.clone(), coord.next_clockwise()])
+            .collect()
+    }
+
+    /// The cells that can be entered from `coord`, respecting the direction of one-way passages.
     pub fn accessible_neighbours(&self, coord: &CircleCoord) -> Vec<CircleCoord> {
+        let mut neighbours = self.passage_neighbours(coord);
+        for [a, b] in &self.portals {
+            if a == coord {
+                neighbours.push(b.clone());
+            } else if b == coord {
+                neighbours.push(a.clone());
+            }
+        }
+
+        if self.one_way.is_empty() {
+            return neighbours;
+        }
+
+        neighbours
+            .into_iter()
+            .filter(|neighbour| !self.one_way.contains(&(neighbour.clone(), coord.clone())))
+            .collect()
+    }
+
+    // The cells connected to `coord` by a passage, whichever way it may be walked.
+    fn passage_neighbours(&self, coord: &CircleCoord) -> Vec<CircleCoord> {
         if coord.circle() == 0 {
             return self.accessible_neighbours_circle_0(coord);
         }
@@ -41,21 +239,63 @@ impl Maze {
 
         if !self.arcs.contains(coord) {
             if coord.circle() == 1 {
-                neighbours.push(CircleCoord::create_with_arc_index(0, 0));
+                neighbours.push(CircleCoord::create_with_arc_index(0, 0, &self.layout));
             } else {
                 neighbours.push(coord.next_in());
             }
         }
 
+        // A gap in the outer boundary leads out of the maze, not into another cell.
+        if coord.circle() + 1 < self.circles {
+            let next_out = coord.next_out();
+            if self.layout.total_arcs(coord.circle()) < self.layout.total_arcs(coord.circle() + 1) {
+                let next_out_cw = next_out.next_clockwise();
+                if !self.arcs.contains(&next_out_cw) {
+                    neighbours.push(next_out_cw);
+                }
+            }
+            if !self.arcs.contains(&next_out) {
+                neighbours.push(next_out);
+            }
+        }
+
+        if !self.crossings.is_empty() {
+            neighbours.extend(self.tunnel_neighbours(coord));
+        }
+
+        neighbours
+    }
+
+    fn tunnel_neighbours(&self, coord: &CircleCoord) -> Vec<CircleCoord> {
+        let mut neighbours = Vec::new();
+        let is_crossing = |c: &CircleCoord, axis: TunnelAxis| self.crossings.get(c) == Some(&axis);
+
+        let next_ccw = coord.next_counter_clockwise();
+        if is_crossing(&next_ccw, TunnelAxis::Ring) {
+            neighbours.push(next_ccw.next_counter_clockwise());
+        }
+
+        let next_cw = coord.next_clockwise();
+        if is_crossing(&next_cw, TunnelAxis::Ring) {
+            neighbours.push(next_cw.next_clockwise());
+        }
+
+        if coord.circle() > 1 {
+            let next_in = coord.next_in();
+            if is_crossing(&next_in, TunnelAxis::Radial) {
+                neighbours.push(next_in.next_in());
+            }
+        }
+
         let next_out = coord.next_out();
-        if calc_total_arcs(coord.circle()) < calc_total_arcs(coord.circle() + 1) {
+        if self.layout.total_arcs(coord.circle()) < self.layout.total_arcs(coord.circle() + 1) {
             let next_out_cw = next_out.next_clockwise();
-            if !self.arcs.contains(&next_out_cw) {
-                neighbours.push(next_out_cw);
+            if is_crossing(&next_out_cw, TunnelAxis::Radial) {
+                neighbours.push(next_out_cw.next_out());
             }
         }
-        if !self.arcs.contains(&next_out) {
-            neighbours.push(next_out);
+        if is_crossing(&next_out, TunnelAxis::Radial) {
+            neighbours.push(next_out.next_out());
         }
 
         neighbours
@@ -64,8 +304,8 @@ impl Maze {
     fn accessible_neighbours_circle_0(&self, _coord: &CircleCoord) -> Vec<CircleCoord> {
         let mut neighbours = Vec::new();
 
-        for arc_index in 0..6 {
-            let coord = CircleCoord::create_with_arc_index(1, arc_index);
+        for arc_index in 0..self.layout.total_arcs(1) {
+            let coord = CircleCoord::create_with_arc_index(1, arc_index, &self.layout);
             if !self.arcs.contains(&coord) {
                 neighbours.push(coord);
             }
@@ -74,8 +314,122 @@ impl Maze {
         neighbours
     }
 
+    /// The number of steps from `start` to every cell reachable from it.
+    pub fn distances(&self, start: &CircleCoord) -> HashMap<CircleCoord, usize> {
+        use std::collections::VecDeque;
+
+        let mut queue = VecDeque::new();
+        let mut distances = HashMap::new();
+
+        queue.push_back(start.clone());
+        distances.insert(start.clone(), 0);
+
+        while let Some(current) = queue.pop_front() {
+            let distance = distances[&current];
+            for neighbor in self.accessible_neighbours(&current) {
+                if !distances.contains_key(&neighbor) {
+                    distances.insert(neighbor.clone(), distance + 1);
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+
+        distances
+    }
+
+    /// Finds the route between two cells with the lowest total cost of the cells entered along
+    /// it, together with that cost, or `None` if `goal` cannot be reached from `start`. Costs
+    /// are added up as `u64`, which no route through cells of `u32` costs can overflow.
+    pub fn cheapest_path(
+        &self,
+        start: &CircleCoord,
+        goal: &CircleCoord,
+    ) -> Option<(u64, Vec<CircleCoord>)> {
+        use std::cmp::Reverse;
+        use std::collections::BinaryHeap;
+
+        // The heap holds indices into `cells`, which are cheaper to compare than coordinates.
+        let mut cells = vec![start.clone()];
+        let mut index = HashMap::from([(start.clone(), 0)]);
+        let mut best: Vec<u64> = vec![0];
+        let mut parent: Vec<Option<usize>> = vec![None];
+        let mut heap = BinaryHeap::from([Reverse((0, 0))]);
+
+        while let Some(Reverse((cost, current))) = heap.pop() {
+            if cost > best[current] {
+                continue;
+            }
+            if &cells[current] == goal {
+                let mut path = Vec::new();
+                let mut node = Some(current);
+                while let Some(n) = node {
+                    path.push(cells[n].clone());
+                    node = parent[n];
+                }
+                path.reverse();
+                return Some((cost, path));
+            }
+
+            for neighbor in self.accessible_neighbours(&cells[current]) {
+                let next_cost = cost + u64::from(self.cost(&neighbor));
+                let next = *index.entry(neighbor.clone()).or_insert_with(|| {
+                    cells.push(neighbor);
+                    best.push(u64::MAX);
+                    parent.push(None);
+                    cells.len() - 1
+                });
+                if next_cost < best[next] {
+                    best[next] = next_cost;
+                    parent[next] = Some(current);
+                    heap.push(Reverse((next_cost, next)));
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Finds the route between two cells, or `None` if `goal` cannot be reached from `start`.
+    pub fn find_path(&self, start: &CircleCoord, goal: &CircleCoord) -> Option<Vec<CircleCoord>> {
+        use std::collections::VecDeque;
+
+        let mut queue = VecDeque::new();
+        let mut parent: HashMap<CircleCoord, Option<CircleCoord>> = HashMap::new();
+
+        queue.push_back(start.clone());
+        parent.insert(start.clone(), None);
+
+        while let Some(current) = queue.pop_front() {
+            if &current == goal {
+                break;
+            }
+
+            for neighbor in self.accessible_neighbours(&current) {
+                if !parent.contains_key(&neighbor) {
+                    parent.insert(neighbor.clone(), Some(current.clone()));
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+
+        if !parent.contains_key(goal) {
+            return None;
+        }
+
+        let mut path = Vec::new();
+        let mut node = Some(goal.clone());
+
+        while let Some(n) = node {
+            path.push(n.clone());
+            node = parent.get(&n).and_then(|p| p.clone());
+        }
+
+        path.reverse();
+        Some(path)
+    }
+
     pub fn tree_diameter(&self) -> Vec<CircleCoord> {
-        let start = CircleCoord::create_with_arc_index(0, 0);
+        let start = CircleCoord::create_with_arc_index(0, 0, &self.layout);
         let first_end = self.find_farthest_node(&start);
         self.find_farthest_with_path(&first_end)
     }
@@ -105,7 +459,7 @@ impl Maze {
     }
 
     fn find_farthest_with_path(&self, start: &CircleCoord) -> Vec<CircleCoord> {
-        use std::collections::{HashMap, VecDeque};
+        use std::collections::VecDeque;
 
         let mut queue = VecDeque::new();
         let mut parent: HashMap<CircleCoord, Option<CircleCoord>> = HashMap::new();
@@ -138,10 +492,90 @@ impl Maze {
     }
 }
 
+pub const SYMMETRY_FOLDS: [usize; 3] = [2, 3, 6];
+
 fn coord_to_index(circle: usize, arc_index: usize, outer: usize) -> usize {
     (circle - 1) * outer + arc_index
 }
 
+// The part of the disc the spanning tree is generated in. A coordinate that leaves the sector is
+// mapped onto its rotated counterpart inside it, so the generated walls can be copied around the
+// disc without creating cycles or disconnected regions.
+struct Sector {
+    outer: usize,
+    folds: usize,
+}
+
+impl Sector {
+    fn sector_arcs(&self, coord: &CircleCoord) -> usize {
+        coord.layout().total_arcs(coord.circle()) / self.folds
+    }
+
+    fn index(&self, coord: &CircleCoord) -> usize {
+        coord_to_index(coord.circle(), coord.arc_index(), self.outer)
+    }
+
+    fn normalize(&self, coord: CircleCoord) -> CircleCoord {
+        let sector_arcs = self.sector_arcs(&coord);
+        if coord.arc_index() < sector_arcs {
+            coord
+        } else {
+            CircleCoord::create_with_arc_index(
+                coord.circle(),
+                coord.arc_index() % sector_arcs,
+                coord.layout(),
+            )
+        }
+    }
+
+    fn rotations(&self, coord: &CircleCoord) -> Vec<CircleCoord> {
+        let sector_arcs = self.sector_arcs(coord);
+        (0..self.folds)
+            .map(|fold| {
+                CircleCoord::create_with_arc_index(
+                    coord.circle(),
+                    coord.arc_index() + fold * sector_arcs,
+                    coord.layout(),
+                )
+            })
+            .collect()
+    }
+
+    fn replicate(&self, walls: TreeWalls) -> TreeWalls {
+        let mut result = TreeWalls::default();
+        for wall in walls.order {
+            let copies: Vec<Wall> = match &wall {
+                Wall::Arc(coord) => self.rotations(coord).into_iter().map(Wall::Arc).collect(),
+                Wall::Line(coord) => self.rotations(coord).into_iter().map(Wall::Line).collect(),
+            };
+            for copy in copies {
+                result.insert(copy);
+            }
+        }
+        result
+    }
+}
+
+// The walls of the spanning tree in the order they were placed.
+#[derive(Default)]
+struct TreeWalls {
+    lines: HashSet<CircleCoord>,
+    arcs: HashSet<CircleCoord>,
+    order: Vec<Wall>,
+}
+
+impl TreeWalls {
+    fn insert(&mut self, wall: Wall) {
+        let added = match &wall {
+            Wall::Arc(coord) => self.arcs.insert(coord.clone()),
+            Wall::Line(coord) => self.lines.insert(coord.clone()),
+        };
+        if added {
+            self.order.push(wall);
+        }
+    }
+}
+
 fn initialize_tracking_vectors(circles: usize, outer: usize) -> (Vec<bool>, Vec<bool>) {
     let total = outer * circles;
     let path = vec![false; total];
@@ -150,12 +584,17 @@ fn initialize_tracking_vectors(circles: usize, outer: usize) -> (Vec<bool>, Vec<
     (path, used)
 }
 
-fn generate_shuffled_coordinates<R: Rng>(circles: usize, rng: &mut R) -> Vec<(usize, usize)> {
+fn generate_shuffled_coordinates<R: Rng>(
+    circles: usize,
+    layout: &RingLayout,
+    folds: usize,
+    rng: &mut R,
+) -> Vec<CircleCoord> {
     let mut free = Vec::new();
     for c in 1..circles {
-        let t = calc_total_arcs(c);
+        let t = layout.total_arcs(c) / folds;
         for arc_index in 0..t {
-            free.push((c, arc_index));
+            free.push(CircleCoord::create_with_arc_index(c, arc_index, layout));
         }
     }
     free.shuffle(rng);
@@ -195,234 +634,393 @@ impl Direction {
     }
 }
 
-fn create_direction_candidates(circle: usize, arc_index: usize) -> Vec<(usize, usize, Direction)> {
+fn create_direction_candidates(coord: &CircleCoord) -> Vec<(CircleCoord, Direction)> {
     vec![
-        (circle, arc_index, Direction::Out),
-        (circle, arc_index, Direction::Clockwise),
-        (circle, arc_index, Direction::In),
-        (circle, arc_index, Direction::CounterClockwise),
+        (coord.clone(), Direction::Out),
+        (coord.clone(), Direction::Clockwise),
+        (coord.clone(), Direction::In),
+        (coord.clone(), Direction::CounterClockwise),
     ]
 }
 
-fn perform_random_walk<R: Rng>(
-    start: (usize, usize),
-    outer: usize,
-    path: &mut [bool],
-    used: &mut [bool],
-    lines: &mut HashSet<CircleCoord>,
-    arcs: &mut HashSet<CircleCoord>,
-    rng: &mut R,
-) {
-    path.fill(false);
-
-    let mut candidates = create_direction_candidates(start.0, start.1);
-    let start_index = coord_to_index(start.0, start.1, outer);
-    path[start_index] = true;
-    used[start_index] = true;
+/// A step of the generator. The generator grows the walls as a tree over the corners where walls
+/// meet, starting from the outer boundary; the passages are whatever the walls leave open.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GenerationEvent {
+    /// A new walk starts from a corner that no wall reaches yet.
+    WalkStarted(CircleCoord),
+    /// The walk reached a new corner and continues from there.
+    CornerVisited(CircleCoord),
+    /// A wall was placed. The walk ends once a wall joins it to the walls already placed.
+    WallAdded(Wall),
+    /// A wall was left out because it would have closed a loop in the current walk, which
+    /// would enclose a region of the maze.
+    LoopRejected(Wall),
+}
 
-    loop {
-        let candidate_index = rng.random_range(0..candidates.len());
-        let candidate = candidates.swap_remove(candidate_index);
+/// The generator run step by step. Every call to `next` performs one step of the walk; `finish`
+/// completes any remaining steps and returns the maze.
+pub struct Generation<R: Rng> {
+    circles: usize,
+    layout: RingLayout,
+    sector: Sector,
+    free: std::vec::IntoIter<CircleCoord>,
+    path: Vec<bool>,
+    used: Vec<bool>,
+    walls: TreeWalls,
+    candidates: Vec<(CircleCoord, Direction)>,
+    pending: Option<GenerationEvent>,
+    rng: R,
+}
 
-        let candidate_coord = CircleCoord::create_with_arc_index(candidate.0, candidate.1);
-        let edge_option = candidate.2.calculate_edge(&candidate_coord);
+impl<R: Rng> Generation<R> {
+    fn new(circles: usize, layout: &RingLayout, folds: usize, mut rng: R) -> Self {
+        let outer = layout.total_arcs(circles);
+        let (path, used) = initialize_tracking_vectors(circles, outer);
+        let free = generate_shuffled_coordinates(circles, layout, folds, &mut rng);
 
-        if edge_option.is_none() {
-            continue;
+        Generation {
+            circles,
+            layout: *layout,
+            sector: Sector { outer, folds },
+            free: free.into_iter(),
+            path,
+            used,
+            walls: TreeWalls::default(),
+            candidates: Vec::new(),
+            pending: None,
+            rng,
         }
+    }
 
-        let (branch, leaf) = edge_option.unwrap();
+    // Starts a walk from the next corner not yet reached, or returns `None` once there is none.
+    fn start_walk(&mut self) -> Option<GenerationEvent> {
+        for start in self.free.by_ref() {
+            let index = self.sector.index(&start);
+            if self.used[index] {
+                continue;
+            }
 
-        let leaf_index = coord_to_index(leaf.circle(), leaf.arc_index(), outer);
-        if path[leaf_index] {
-            continue;
+            self.path.fill(false);
+            self.candidates = create_direction_candidates(&start);
+            self.path[index] = true;
+            self.used[index] = true;
+            return Some(GenerationEvent::WalkStarted(start));
         }
+        None
+    }
 
-        candidates.extend(create_direction_candidates(leaf.circle(), leaf.arc_index()));
+    fn step_walk(&mut self) -> Option<GenerationEvent> {
+        loop {
+            let candidate_index = self.rng.random_range(0..self.candidates.len());
+            let (candidate_coord, direction) = self.candidates.swap_remove(candidate_index);
 
-        let edge = if candidate.2.uses_branch() {
-            branch
-        } else {
-            leaf
-        };
-        if candidate.2.is_arc_direction() {
-            arcs.insert(edge);
-        } else {
-            lines.insert(edge);
-        }
+            let Some((branch, leaf)) = direction.calculate_edge(&candidate_coord) else {
+                continue;
+            };
+            let leaf = self.sector.normalize(leaf);
 
-        if used[leaf_index] {
-            break;
+            let edge = if direction.uses_branch() {
+                branch
+            } else {
+                leaf.clone()
+            };
+            let wall = if direction.is_arc_direction() {
+                Wall::Arc(edge)
+            } else {
+                Wall::Line(edge)
+            };
+
+            let leaf_index = self.sector.index(&leaf);
+            if self.path[leaf_index] {
+                return Some(GenerationEvent::LoopRejected(wall));
+            }
+
+            self.candidates.extend(create_direction_candidates(&leaf));
+            self.walls.insert(wall.clone());
+
+            if self.used[leaf_index] {
+                self.candidates.clear();
+            } else {
+                self.path[leaf_index] = true;
+                self.used[leaf_index] = true;
+                self.pending = Some(GenerationEvent::CornerVisited(leaf));
+            }
+            return Some(GenerationEvent::WallAdded(wall));
         }
+    }
 
-        path[leaf_index] = true;
-        used[leaf_index] = true;
+    /// Runs the remaining steps and returns the maze together with its inner walls in the order
+    /// they were placed.
+    pub fn finish(mut self) -> (Maze, Vec<Wall>) {
+        for _ in self.by_ref() {}
+
+        let walls = self.sector.replicate(self.walls);
+        let arcs = add_outer_boundary(walls.arcs, self.circles, &self.layout);
+        let maze = Maze {
+            circles: self.circles,
+            layout: self.layout,
+            arcs,
+            lines: walls.lines,
+            crossings: HashMap::new(),
+            one_way: HashSet::new(),
+            portals: Vec::new(),
+            costs: HashMap::new(),
+            metadata: Metadata::default(),
+        };
+        (maze, walls.order)
     }
 }
 
-fn build_spanning_tree<R: Rng>(
-    free: Vec<(usize, usize)>,
-    outer: usize,
-    path: &mut [bool],
-    used: &mut [bool],
-    rng: &mut R,
-) -> (HashSet<CircleCoord>, HashSet<CircleCoord>) {
-    let mut lines = HashSet::new();
-    let mut arcs = HashSet::new();
+impl<R: Rng> Iterator for Generation<R> {
+    type Item = GenerationEvent;
 
-    for f in free {
-        let index = coord_to_index(f.0, f.1, outer);
-        if used[index] {
-            continue;
+    fn next(&mut self) -> Option<GenerationEvent> {
+        if let Some(event) = self.pending.take() {
+            return Some(event);
         }
-
-        perform_random_walk(f, outer, path, used, &mut lines, &mut arcs, rng);
+        if self.candidates.is_empty() {
+            return self.start_walk();
+        }
+        self.step_walk()
     }
+}
 
-    (lines, arcs)
+/// A generator step as the JSON object `--events` prints and `GenerationStream` hands out.
+pub fn serialize_generation_event(event: &GenerationEvent) -> Value {
+    let coord_json = |kind: &str, coord: &CircleCoord| {
+        json!({ "type": kind, "circle": coord.circle(), "arc": coord.arc_index() })
+    };
+    let wall_json = |kind: &str, wall: &Wall| {
+        let (wall_type, coord) = match wall {
+            Wall::Arc(coord) => ("arc", coord),
+            Wall::Line(coord) => ("line", coord),
+        };
+        let mut value = coord_json(kind, coord);
+        value["wall"] = Value::from(wall_type);
+        value
+    };
+
+    match event {
+        GenerationEvent::WalkStarted(coord) => coord_json("walk_started", coord),
+        GenerationEvent::CornerVisited(coord) => coord_json("corner_visited", coord),
+        GenerationEvent::WallAdded(wall) => wall_json("wall_added", wall),
+        GenerationEvent::LoopRejected(wall) => wall_json("loop_rejected", wall),
+    }
 }
 
-fn add_outer_boundary(mut arcs: HashSet<CircleCoord>, circles: usize) -> HashSet<CircleCoord> {
-    let outer = calc_total_arcs(circles);
+fn add_outer_boundary(
+    mut arcs: HashSet<CircleCoord>,
+    circles: usize,
+    layout: &RingLayout,
+) -> HashSet<CircleCoord> {
+    let outer = layout.total_arcs(circles);
     for i in 0..outer {
-        arcs.insert(CircleCoord::create_with_arc_index(circles, i));
+        arcs.insert(CircleCoord::create_with_arc_index(circles, i, layout));
     }
     arcs
 }
 
-pub fn factory<R: Rng>(circles: usize, rng: &mut R) -> Maze {
-    let outer = calc_total_arcs(circles);
-    let (mut path, mut used) = initialize_tracking_vectors(circles, outer);
-    let free = generate_shuffled_coordinates(circles, rng);
+fn generate<R: Rng>(
+    circles: usize,
+    layout: &RingLayout,
+    folds: usize,
+    rng: &mut R,
+) -> (Maze, Vec<Wall>) {
+    Generation::new(circles, layout, folds, rng).finish()
+}
+
+pub fn factory<R: Rng>(circles: usize, layout: &RingLayout, rng: &mut R) -> Maze {
+    generate(circles, layout, 1, rng).0
+}
 
-    let (lines, arcs) = build_spanning_tree(free, outer, &mut path, &mut used, rng);
-    let arcs = add_outer_boundary(arcs, circles);
+/// Runs the generator of `factory` step by step. Call `finish` on the result to get the maze.
+pub fn generation_events<R: Rng>(circles: usize, layout: &RingLayout, rng: R) -> Generation<R> {
+    Generation::new(circles, layout, 1, rng)
+}
 
-    Maze {
-        circles,
-        arcs,
-        lines,
+/// Generates a maze like `factory` and also returns its walls in the order the generator placed
+/// them, starting from the outer boundary, which is not listed.
+pub fn factory_with_history<R: Rng>(
+    circles: usize,
+    layout: &RingLayout,
+    rng: &mut R,
+) -> (Maze, Vec<Wall>) {
+    generate(circles, layout, 1, rng)
+}
+
+/// Generates a maze with n-fold rotational symmetry. One sector is generated and copied around
+/// the disc; the copies together still form a single spanning tree.
+pub fn symmetric_factory<R: Rng>(
+    circles: usize,
+    layout: &RingLayout,
+    folds: usize,
+    rng: &mut R,
+) -> Result<Maze, MazeError> {
+    if !SYMMETRY_FOLDS.contains(&folds) {
+        return Err(MazeError::options(format!("symmetry must be one of {:?}", SYMMETRY_FOLDS)));
     }
+
+    if !layout.first_ring_arcs().is_multiple_of(folds) {
+        return Err(MazeError::options(format!(
+            "{}-fold symmetry requires the first ring arcs ({}) to be a multiple of {}",
+            folds,
+            layout.first_ring_arcs(),
+            folds
+        )));
+    }
+
+    Ok(generate(circles, layout, folds, rng).0)
 }
 
 pub struct MazeDeserializer;
 
 impl MazeDeserializer {
-    pub fn deserialize(data: Value) -> Result<Maze, String> {
-        let obj = data.as_object().ok_or("Input must be a JSON object")?;
-
-        let circles = obj
-            .get("circles")
-            .ok_or("Missing 'circles' field")?
-            .as_u64()
-            .ok_or("'circles' must be a number")? as usize;
-
-        let arcs_array = obj
-            .get("arcs")
-            .ok_or("Missing 'arcs' field")?
-            .as_array()
-            .ok_or("'arcs' must be an array")?;
-
-        let mut arcs = HashSet::new();
-        for (i, arc_obj) in arcs_array.iter().enumerate() {
-            let arc_map = arc_obj
-                .as_object()
-                .ok_or(format!("arcs[{}] must be an object", i))?;
-
-            let circle = arc_map
-                .get("circle")
-                .ok_or(format!("arcs[{}] missing 'circle' field", i))?
-                .as_u64()
-                .ok_or(format!("arcs[{}].circle must be a number", i))?
-                as usize;
-
-            let arc = arc_map
-                .get("arc")
-                .ok_or(format!("arcs[{}] missing 'arc' field", i))?
-                .as_u64()
-                .ok_or(format!("arcs[{}].arc must be a number", i))? as usize;
-
-            let coord = CircleCoord::create_with_arc_index(circle, arc);
-            arcs.insert(coord);
-        }
-
-        let lines_array = obj
-            .get("lines")
-            .ok_or("Missing 'lines' field")?
-            .as_array()
-            .ok_or("'lines' must be an array")?;
+    /// Reads a saved maze of any format version.
+    pub fn deserialize(data: Value) -> Result<Maze, MazeError> {
+        Self::from_file(&Self::read_file(data)?)
+    }
+
+    /// Upgrades a saved maze to the current format and reads it into its typed form.
+    pub fn read_file(data: Value) -> Result<MazeFile, MazeError> {
+        serde_path_to_error::deserialize(migrate(data)?).map_err(|e| {
+            let path = e.path().to_string();
+            let path = if path == "." { "" } else { &path };
+            MazeError::schema(path, e.into_inner().to_string())
+        })
+    }
+
+    /// Builds the maze a file describes. Values that are well-formed but impossible, such as a
+    /// one-way passage between cells that do not touch, are all reported together.
+    pub fn from_file(file: &MazeFile) -> Result<Maze, MazeError> {
+        let layout = file.layout.to_layout().map_err(|error| {
+            let (path, message) = (Some(String::from("layout")), error.to_string());
+            MazeError::Semantic(vec![Diagnostic::new(DiagnosticKind::InvalidValue, path, message)])
+        })?;
+
+        // Every cell is checked against the layout before any coordinate is built from it.
+        let outside: Vec<Diagnostic> = file
+            .cells()
+            .into_iter()
+            .filter(|(_, cell)| cell.to_coord(&layout).is_none())
+            .map(|(path, cell)| {
+                let message =
+                    format!("{}: cell ({}, {}) is outside the maze", path, cell.circle, cell.arc);
+                Diagnostic::new(DiagnosticKind::OutOfRange, Some(path), message)
+            })
+            .collect();
+        if !outside.is_empty() {
+            return Err(MazeError::Semantic(outside));
+        }
+        if layout.checked_total_arcs(file.circles).is_none() {
+            let message = format!("circles: {} rings have too many arcs to count", file.circles);
+            let path = Some(String::from("circles"));
+            let diagnostic = Diagnostic::new(DiagnosticKind::TooLarge, path, message);
+            return Err(MazeError::Semantic(vec![diagnostic]));
+        }
 
-        let mut lines = HashSet::new();
-        for (i, line_obj) in lines_array.iter().enumerate() {
-            let line_map = line_obj
-                .as_object()
-                .ok_or(format!("lines[{}] must be an object", i))?;
+        let cell = |cell: &CellFile| cell.to_coord(&layout).expect("The cell was checked");
+        let mut problems = Vec::new();
 
-            let circle = line_map
-                .get("circle")
-                .ok_or(format!("lines[{}] missing 'circle' field", i))?
-                .as_u64()
-                .ok_or(format!("lines[{}].circle must be a number", i))?
-                as usize;
+        let mut one_way = HashSet::new();
+        for (i, passage) in file.one_way.iter().enumerate() {
+            let (from, to) = (cell(&passage.from), cell(&passage.to));
+            if Wall::between(&from, &to).is_none() {
+                let path = format!("one_way[{}]", i);
+                let message = format!("{}: the cells are not adjacent", path);
+                problems.push(Diagnostic::new(DiagnosticKind::NotAdjacent, Some(path), message));
+            }
+            one_way.insert((from, to));
+        }
 
-            let arc = line_map
-                .get("arc")
-                .ok_or(format!("lines[{}] missing 'arc' field", i))?
-                .as_u64()
-                .ok_or(format!("lines[{}].arc must be a number", i))?
-                as usize;
+        let mut costs = HashMap::new();
+        for (i, entry) in file.costs.iter().enumerate() {
+            if entry.cost == 0 {
+                let path = format!("costs[{}].cost", i);
+                let message = format!("{}: must be a positive number", path);
+                problems.push(Diagnostic::new(DiagnosticKind::InvalidValue, Some(path), message));
+            }
+            costs.insert(cell(&entry.cell), entry.cost);
+        }
 
-            let coord = CircleCoord::create_with_arc_index(circle, arc);
-            lines.insert(coord);
+        if !problems.is_empty() {
+            return Err(MazeError::Semantic(problems));
         }
 
         Ok(Maze {
-            circles,
-            arcs,
-            lines,
+            circles: file.circles,
+            layout,
+            arcs: file.arcs.iter().map(cell).collect(),
+            lines: file.lines.iter().map(cell).collect(),
+            crossings: file.crossings.iter().map(|c| (cell(&c.cell), c.tunnel)).collect(),
+            one_way,
+            portals: file.portals.iter().map(|[a, b]| [cell(a), cell(b)]).collect(),
+            costs,
+            metadata: file.metadata.as_ref().map(|m| m.to_metadata(&layout)).unwrap_or_default(),
         })
     }
+
+    /// Builds a maze that has nothing but the given walls.
+    pub fn from_walls(circles: usize, layout: &RingLayout, walls: Vec<Wall>) -> Maze {
+        let mut maze = Maze {
+            circles,
+            layout: *layout,
+            arcs: HashSet::new(),
+            lines: HashSet::new(),
+            crossings: HashMap::new(),
+            one_way: HashSet::new(),
+            portals: Vec::new(),
+            costs: HashMap::new(),
+            metadata: Metadata::default(),
+        };
+        for wall in walls {
+            maze.add_wall(wall);
+        }
+        maze
+    }
 }
 
 pub struct MazeSerializer;
 
 impl MazeSerializer {
+    /// Writes the maze with its cells sorted by ring and arc, so that saving the same maze twice
+    /// gives the same file.
     pub fn serialize(maze: &Maze) -> Value {
-        use serde_json::json;
-
-        let arcs_array: Vec<Value> = maze
-            .arcs()
-            .iter()
-            .map(|coord| {
-                json!({
-                    "circle": coord.circle(),
-                    "arc": coord.arc_index()
-                })
-            })
-            .collect();
-
-        let lines_array: Vec<Value> = maze
-            .lines()
-            .iter()
-            .map(|coord| {
-                json!({
-                    "circle": coord.circle(),
-                    "arc": coord.arc_index()
-                })
-            })
-            .collect();
+        let file = MazeFile {
+            format_version: FORMAT_VERSION,
+            circles: maze.circles(),
+            layout: LayoutFile::from(maze.layout()),
+            arcs: BTreeSet::from_iter(maze.arcs()).into_iter().map(CellFile::from).collect(),
+            lines: BTreeSet::from_iter(maze.lines()).into_iter().map(CellFile::from).collect(),
+            crossings: BTreeMap::from_iter(maze.crossings())
+                .into_iter()
+                .map(|(coord, axis)| CrossingFile { cell: CellFile::from(coord), tunnel: *axis })
+                .collect(),
+            one_way: BTreeSet::from_iter(maze.one_way())
+                .into_iter()
+                .map(|(from, to)| OneWayFile { from: CellFile::from(from), to: CellFile::from(to) })
+                .collect(),
+            portals: maze
+                .portals()
+                .iter()
+                .map(|[a, b]| [CellFile::from(a), CellFile::from(b)])
+                .collect(),
+            costs: BTreeMap::from_iter(maze.costs())
+                .into_iter()
+                .map(|(coord, cost)| CostFile { cell: CellFile::from(coord), cost: *cost })
+                .collect(),
+            metadata: (!maze.metadata().is_empty()).then(|| MetadataFile::from(maze.metadata())),
+        };
 
-        json!({
-            "circles": maze.circles(),
-            "arcs": arcs_array,
-            "lines": lines_array
-        })
+        serde_json::to_value(&file).expect("A maze always converts to JSON")
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::circle_coord::Subdivision;
+    use crate::dsl::read_dsl;
 
     #[test]
     fn test_factory_creates_spanning_tree() {
@@ -435,11 +1033,11 @@ mod tests {
         for circles in 3..10 {
             for &seed in &seeds {
                 let mut rng = StdRng::seed_from_u64(seed);
-                let maze = factory(circles, &mut rng);
+                let maze = factory(circles, &RingLayout::default(), &mut rng);
 
                 let mut vertice_count = 0;
                 for c in 1..=circles {
-                    vertice_count += calc_total_arcs(c);
+                    vertice_count += maze.layout().total_arcs(c);
                 }
 
                 assert_eq!(
@@ -451,7 +1049,7 @@ mod tests {
                 );
 
                 let mut visited = HashSet::new();
-                let mut stack = vec![CircleCoord::create_with_arc_index(0, 0)];
+                let mut stack = vec![CircleCoord::create_with_arc_index(0, 0, maze.layout())];
 
                 while let Some(v) = stack.pop() {
                     if visited.contains(&v) {
@@ -466,7 +1064,7 @@ mod tests {
 
                 let mut reachable_vertice_count = 1;
                 for c in 1..circles {
-                    reachable_vertice_count += calc_total_arcs(c);
+                    reachable_vertice_count += maze.layout().total_arcs(c);
                 }
 
                 assert_eq!(
@@ -492,90 +1090,90 @@ mod tests {
         assert_eq!(maze.circles(), 3, "Maze should have 3 circles");
     }
 
+    // The maze of tests/fixtures/maze_05_circles_00.json.
+    const FIVE_CIRCLES: &str = "
+        ring 1  arcs ____._                    lines ||..||
+        ring 2  arcs _...._._..._              lines ..|||.|||||.
+        ring 3  arcs ______....._              lines ..|....|||||
+        ring 4  arcs ...__.__.._.___.._...___  lines .||.....||.|.....|.|...|
+        ring 5  arcs ________________________
+    ";
+
     #[test]
-    fn test_accessible_neighbours_4() {
+    fn test_five_circles_text_matches_the_fixture() {
         let json_str = include_str!("../tests/fixtures/maze_05_circles_00.json");
-        let json_data: Value = serde_json::from_str(json_str).unwrap();
+        let fixture = MazeDeserializer::deserialize(serde_json::from_str(json_str).unwrap());
+        let maze = read_dsl(FIVE_CIRCLES).unwrap();
 
-        let maze = MazeDeserializer::deserialize(json_data).unwrap();
-        let coord = CircleCoord::create_with_arc_index(3, 4);
+        assert_eq!(MazeSerializer::serialize(&maze), MazeSerializer::serialize(&fixture.unwrap()));
+    }
+
+    #[test]
+    fn test_accessible_neighbours_4() {
+        let maze = read_dsl(FIVE_CIRCLES).unwrap();
+        let coord = CircleCoord::create_with_arc_index(3, 4, maze.layout());
         let neighbours = maze.accessible_neighbours(&coord);
 
         assert_eq!(neighbours.len(), 4);
-        assert!(neighbours.contains(&CircleCoord::create_with_arc_index(3, 3)));
-        assert!(neighbours.contains(&CircleCoord::create_with_arc_index(3, 5)));
-        assert!(neighbours.contains(&CircleCoord::create_with_arc_index(4, 8)));
-        assert!(neighbours.contains(&CircleCoord::create_with_arc_index(4, 9)));
+        assert!(neighbours.contains(&CircleCoord::create_with_arc_index(3, 3, maze.layout())));
+        assert!(neighbours.contains(&CircleCoord::create_with_arc_index(3, 5, maze.layout())));
+        assert!(neighbours.contains(&CircleCoord::create_with_arc_index(4, 8, maze.layout())));
+        assert!(neighbours.contains(&CircleCoord::create_with_arc_index(4, 9, maze.layout())));
     }
 
     #[test]
     fn test_accessible_neighbours_9() {
-        let json_str = include_str!("../tests/fixtures/maze_05_circles_00.json");
-        let json_data: Value = serde_json::from_str(json_str).unwrap();
-
-        let maze = MazeDeserializer::deserialize(json_data).unwrap();
-        let coord = CircleCoord::create_with_arc_index(3, 9);
+        let maze = read_dsl(FIVE_CIRCLES).unwrap();
+        let coord = CircleCoord::create_with_arc_index(3, 9, maze.layout());
         let neighbours = maze.accessible_neighbours(&coord);
 
         assert_eq!(neighbours.len(), 3);
-        assert!(neighbours.contains(&CircleCoord::create_with_arc_index(2, 9)));
-        assert!(neighbours.contains(&CircleCoord::create_with_arc_index(4, 18)));
-        assert!(neighbours.contains(&CircleCoord::create_with_arc_index(4, 19)));
+        assert!(neighbours.contains(&CircleCoord::create_with_arc_index(2, 9, maze.layout())));
+        assert!(neighbours.contains(&CircleCoord::create_with_arc_index(4, 18, maze.layout())));
+        assert!(neighbours.contains(&CircleCoord::create_with_arc_index(4, 19, maze.layout())));
     }
 
     #[test]
     fn test_accessible_neighbours_arc_10() {
-        let json_str = include_str!("../tests/fixtures/maze_05_circles_00.json");
-        let json_data: Value = serde_json::from_str(json_str).unwrap();
-
-        let maze = MazeDeserializer::deserialize(json_data).unwrap();
-        let coord = CircleCoord::create_with_arc_index(3, 10);
+        let maze = read_dsl(FIVE_CIRCLES).unwrap();
+        let coord = CircleCoord::create_with_arc_index(3, 10, maze.layout());
         let neighbours = maze.accessible_neighbours(&coord);
 
         assert_eq!(neighbours.len(), 2);
-        assert!(neighbours.contains(&CircleCoord::create_with_arc_index(2, 10)));
-        assert!(neighbours.contains(&CircleCoord::create_with_arc_index(4, 20)));
+        assert!(neighbours.contains(&CircleCoord::create_with_arc_index(2, 10, maze.layout())));
+        assert!(neighbours.contains(&CircleCoord::create_with_arc_index(4, 20, maze.layout())));
     }
 
     #[test]
     fn test_accessible_neighbours_arc_11() {
-        let json_str = include_str!("../tests/fixtures/maze_05_circles_00.json");
-        let json_data: Value = serde_json::from_str(json_str).unwrap();
-
-        let maze = MazeDeserializer::deserialize(json_data).unwrap();
-        let coord = CircleCoord::create_with_arc_index(3, 11);
+        let maze = read_dsl(FIVE_CIRCLES).unwrap();
+        let coord = CircleCoord::create_with_arc_index(3, 11, maze.layout());
         let neighbours = maze.accessible_neighbours(&coord);
 
         assert_eq!(neighbours.len(), 1);
-        assert!(neighbours.contains(&CircleCoord::create_with_arc_index(3, 0)));
+        assert!(neighbours.contains(&CircleCoord::create_with_arc_index(3, 0, maze.layout())));
     }
 
     #[test]
     fn test_accessible_neighbours_circle_0() {
-        let json_str = include_str!("../tests/fixtures/maze_05_circles_00.json");
-        let json_data: Value = serde_json::from_str(json_str).unwrap();
-
-        let maze = MazeDeserializer::deserialize(json_data).unwrap();
-        let coord = CircleCoord::create_with_arc_index(0, 0);
+        let maze = read_dsl(FIVE_CIRCLES).unwrap();
+        let coord = CircleCoord::create_with_arc_index(0, 0, maze.layout());
         let neighbours = maze.accessible_neighbours(&coord);
 
         assert_eq!(neighbours.len(), 1);
-        assert!(neighbours.contains(&CircleCoord::create_with_arc_index(1, 4)));
+        assert!(neighbours.contains(&CircleCoord::create_with_arc_index(1, 4, maze.layout())));
     }
 
     #[test]
     fn test_accessible_neighbours_circle_1() {
-        let json_str = include_str!("../tests/fixtures/maze_05_circles_00.json");
-        let json_data: Value = serde_json::from_str(json_str).unwrap();
-
-        let maze = MazeDeserializer::deserialize(json_data).unwrap();
-        let coord = CircleCoord::create_with_arc_index(1, 4);
+        let maze = read_dsl(FIVE_CIRCLES).unwrap();
+        let coord = CircleCoord::create_with_arc_index(1, 4, maze.layout());
         let neighbours = maze.accessible_neighbours(&coord);
 
         assert_eq!(neighbours.len(), 3);
-        assert!(neighbours.contains(&CircleCoord::create_with_arc_index(0, 0)));
-        assert!(neighbours.contains(&CircleCoord::create_with_arc_index(2, 8)));
-        assert!(neighbours.contains(&CircleCoord::create_with_arc_index(2, 9)));
+        assert!(neighbours.contains(&CircleCoord::create_with_arc_index(0, 0, maze.layout())));
+        assert!(neighbours.contains(&CircleCoord::create_with_arc_index(2, 8, maze.layout())));
+        assert!(neighbours.contains(&CircleCoord::create_with_arc_index(2, 9, maze.layout())));
     }
 
     #[test]
@@ -584,6 +1182,7 @@ mod tests {
         let original_json: Value = serde_json::from_str(json_str).unwrap();
         let maze = MazeDeserializer::deserialize(original_json.clone()).unwrap();
         let serialized = MazeSerializer::serialize(&maze);
+        assert_eq!(serialized["format_version"], Value::from(FORMAT_VERSION));
         let deserialized_maze = MazeDeserializer::deserialize(serialized).unwrap();
 
         assert_eq!(maze.circles(), deserialized_maze.circles());
@@ -597,7 +1196,7 @@ mod tests {
         use rand::SeedableRng;
 
         let mut rng = StdRng::seed_from_u64(42);
-        let maze = factory(4, &mut rng);
+        let maze = factory(4, &RingLayout::default(), &mut rng);
         let serialized = MazeSerializer::serialize(&maze);
         let deserialized = MazeDeserializer::deserialize(serialized).unwrap();
 
@@ -605,4 +1204,409 @@ mod tests {
         assert_eq!(maze.arcs(), deserialized.arcs());
         assert_eq!(maze.lines(), deserialized.lines());
     }
+
+    #[test]
+    fn test_serialize_is_canonical() {
+        use crate::terrain::terrain_factory;
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        let mut rng = StdRng::seed_from_u64(42);
+        let maze = terrain_factory(6, &RingLayout::default(), 3, &mut rng);
+        let serialized = MazeSerializer::serialize(&maze);
+
+        // A reloaded maze holds its cells in differently seeded hash sets.
+        let reloaded = MazeDeserializer::deserialize(serialized.clone()).unwrap();
+        assert_eq!(
+            serde_json::to_string_pretty(&MazeSerializer::serialize(&reloaded)).unwrap(),
+            serde_json::to_string_pretty(&serialized).unwrap()
+        );
+
+        let cells: Vec<(u64, u64)> = serialized["arcs"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|arc| (arc["circle"].as_u64().unwrap(), arc["arc"].as_u64().unwrap()))
+            .collect();
+        assert!(cells.is_sorted());
+    }
+
+    #[test]
+    fn test_factory_with_width_ratio_layout() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        let layout = RingLayout::new(5, Subdivision::WidthRatio(1.2)).unwrap();
+        let mut rng = StdRng::seed_from_u64(42);
+        let maze = factory(8, &layout, &mut rng);
+
+        let vertice_count: usize = (1..=8).map(|c| layout.total_arcs(c)).sum();
+        assert_eq!(vertice_count, maze.arcs().len() + maze.lines().len());
+
+        let mut visited = HashSet::new();
+        let mut stack = vec![CircleCoord::create_with_arc_index(0, 0, &layout)];
+        while let Some(v) = stack.pop() {
+            if visited.insert(v.clone()) {
+                stack.extend(maze.accessible_neighbours(&v));
+            }
+        }
+
+        let reachable_vertice_count: usize = (1..8).map(|c| layout.total_arcs(c)).sum();
+        assert_eq!(visited.len(), reachable_vertice_count + 1);
+    }
+
+    #[test]
+    fn test_layout_roundtrip() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        let layout = RingLayout::new(8, Subdivision::WidthRatio(1.5)).unwrap();
+        let mut rng = StdRng::seed_from_u64(7);
+        let maze = factory(6, &layout, &mut rng);
+        let serialized = MazeSerializer::serialize(&maze);
+        let deserialized = MazeDeserializer::deserialize(serialized).unwrap();
+
+        assert_eq!(maze.layout(), deserialized.layout());
+        assert_eq!(maze.arcs(), deserialized.arcs());
+        assert_eq!(maze.lines(), deserialized.lines());
+    }
+
+    #[test]
+    fn test_deserialize_without_layout_uses_default() {
+        let json_str = include_str!("../tests/fixtures/maze_03_circles_00.json");
+        let json_data: Value = serde_json::from_str(json_str).unwrap();
+        let maze = MazeDeserializer::deserialize(json_data).unwrap();
+
+        assert_eq!(maze.layout(), &RingLayout::default());
+    }
+
+    #[test]
+    fn test_deserialize_rejects_invalid_layout() {
+        let json_data = serde_json::json!({
+            "circles": 3,
+            "layout": {"first_ring_arcs": 6, "subdivision": "width_ratio", "ratio": 0.1},
+            "arcs": [],
+            "lines": []
+        });
+
+        assert!(MazeDeserializer::deserialize(json_data).is_err());
+    }
+
+    #[test]
+    fn test_deserialize_rejects_cells_outside_the_layout() {
+        for (circle, arc) in [(1u64, 6u64), (1, u64::MAX), (u64::MAX, 0)] {
+            let json_data = serde_json::json!({
+                "circles": 2,
+                "arcs": [],
+                "lines": [],
+                "portals": [[{ "circle": 1, "arc": 0 }, { "circle": circle, "arc": arc }]]
+            });
+
+            let error = MazeDeserializer::deserialize(json_data).unwrap_err();
+            assert_eq!(error.path(), Some("portals[0][1]"));
+        }
+    }
+
+    #[test]
+    fn test_symmetric_factory_creates_symmetric_spanning_tree() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        let layout = RingLayout::default();
+
+        for folds in SYMMETRY_FOLDS {
+            for circles in 3..9 {
+                let mut rng = StdRng::seed_from_u64(circles as u64);
+                let maze = symmetric_factory(circles, &layout, folds, &mut rng).unwrap();
+
+                let vertice_count: usize = (1..=circles).map(|c| layout.total_arcs(c)).sum();
+                assert_eq!(
+                    vertice_count,
+                    maze.arcs().len() + maze.lines().len(),
+                    "{}-fold maze with {} circles should form a spanning tree",
+                    folds,
+                    circles
+                );
+
+                let mut visited = HashSet::new();
+                let mut stack = vec![CircleCoord::create_with_arc_index(0, 0, &layout)];
+                while let Some(v) = stack.pop() {
+                    if visited.insert(v.clone()) {
+                        stack.extend(maze.accessible_neighbours(&v));
+                    }
+                }
+                let reachable_vertice_count: usize =
+                    (1..circles).map(|c| layout.total_arcs(c)).sum();
+                assert_eq!(visited.len(), reachable_vertice_count + 1);
+
+                let rotate = |coord: &CircleCoord| {
+                    let total = layout.total_arcs(coord.circle());
+                    let arc_index = (coord.arc_index() + total / folds) % total;
+                    CircleCoord::create_with_arc_index(coord.circle(), arc_index, &layout)
+                };
+                assert!(maze.arcs().iter().all(|arc| maze.arcs().contains(&rotate(arc))));
+                assert!(maze.lines().iter().all(|line| maze.lines().contains(&rotate(line))));
+            }
+        }
+    }
+
+    #[test]
+    fn test_symmetric_factory_rejects_invalid_folds() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        let mut rng = StdRng::seed_from_u64(42);
+        let layout = RingLayout::default();
+        assert!(symmetric_factory(5, &layout, 4, &mut rng).is_err());
+
+        let layout = RingLayout::new(8, Subdivision::PowerOfTwo).unwrap();
+        assert!(symmetric_factory(5, &layout, 3, &mut rng).is_err());
+    }
+
+    #[test]
+    fn test_crossings_roundtrip() {
+        use crate::weave::weave_factory;
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        let mut rng = StdRng::seed_from_u64(42);
+        let maze = weave_factory(8, &RingLayout::default(), 5, &mut rng);
+        let serialized = MazeSerializer::serialize(&maze);
+        let deserialized = MazeDeserializer::deserialize(serialized).unwrap();
+
+        assert!(!maze.crossings().is_empty());
+        assert_eq!(maze.crossings(), deserialized.crossings());
+        assert_eq!(maze.arcs(), deserialized.arcs());
+        assert_eq!(maze.lines(), deserialized.lines());
+    }
+
+    #[test]
+    fn test_one_way_roundtrip() {
+        use crate::one_way::one_way_factory;
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        let mut rng = StdRng::seed_from_u64(42);
+        let maze = one_way_factory(6, &RingLayout::default(), 10, &mut rng);
+        let serialized = MazeSerializer::serialize(&maze);
+        let deserialized = MazeDeserializer::deserialize(serialized).unwrap();
+
+        assert_eq!(maze.one_way().len(), 10);
+        assert_eq!(maze.one_way(), deserialized.one_way());
+    }
+
+    #[test]
+    fn test_one_way_rejects_cells_that_are_not_adjacent() {
+        let json_data = serde_json::json!({
+            "circles": 3,
+            "arcs": [],
+            "lines": [],
+            "one_way": [{ "from": { "circle": 1, "arc": 0 }, "to": { "circle": 2, "arc": 5 } }]
+        });
+
+        assert!(MazeDeserializer::deserialize(json_data).is_err());
+    }
+
+    #[test]
+    fn test_portals_roundtrip() {
+        use crate::portals::portal_factory;
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        let mut rng = StdRng::seed_from_u64(42);
+        let maze = portal_factory(8, &RingLayout::default(), 3, &mut rng).unwrap();
+        let serialized = MazeSerializer::serialize(&maze);
+        let deserialized = MazeDeserializer::deserialize(serialized).unwrap();
+
+        assert_eq!(maze.portals().len(), 3);
+        assert_eq!(maze.portals(), deserialized.portals());
+    }
+
+    #[test]
+    fn test_cheapest_path_avoids_expensive_cells() {
+        let layout = RingLayout::default();
+        let coord = |circle, arc| CircleCoord::create_with_arc_index(circle, arc, &layout);
+        let outer: Vec<Value> =
+            (0..12).map(|arc| serde_json::json!({ "circle": 2, "arc": arc })).collect();
+        let json_data = serde_json::json!({ "circles": 2, "arcs": outer, "lines": [] });
+
+        let mut maze = MazeDeserializer::deserialize(json_data).unwrap();
+        let (cost, path) = maze.cheapest_path(&coord(1, 0), &coord(1, 3)).unwrap();
+        assert_eq!(cost, 2);
+        assert_eq!(path, vec![coord(1, 0), coord(0, 0), coord(1, 3)]);
+
+        maze.set_cost(coord(0, 0), 10);
+        let (cost, path) = maze.cheapest_path(&coord(1, 0), &coord(1, 3)).unwrap();
+        assert_eq!(cost, 3);
+        assert_eq!(path.len(), 4);
+        assert!(!path.contains(&coord(0, 0)));
+    }
+
+    #[test]
+    fn test_cheapest_path_does_not_overflow_on_huge_costs() {
+        let layout = RingLayout::default();
+        let coord = |circle, arc| CircleCoord::create_with_arc_index(circle, arc, &layout);
+        let outer: Vec<Value> =
+            (0..12).map(|arc| serde_json::json!({ "circle": 3, "arc": arc })).collect();
+        let dear = u32::MAX - 1;
+        let costs: Vec<Value> = (1..3)
+            .flat_map(|circle| (0..layout.total_arcs(circle)).map(move |arc| (circle, arc)))
+            .map(|(circle, arc)| serde_json::json!({ "circle": circle, "arc": arc, "cost": dear }))
+            .collect();
+        let json_data =
+            serde_json::json!({ "circles": 3, "arcs": outer, "lines": [], "costs": costs });
+
+        let maze = MazeDeserializer::deserialize(json_data).unwrap();
+        let (cost, path) = maze.cheapest_path(&coord(2, 0), &coord(2, 6)).unwrap();
+        assert_eq!(cost, (path.len() as u64 - 2) * u64::from(dear) + 1);
+        assert!(cost > u64::from(u32::MAX));
+    }
+
+    #[test]
+    fn test_costs_roundtrip() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        let layout = RingLayout::default();
+        let mut rng = StdRng::seed_from_u64(42);
+        let mut maze = factory(4, &layout, &mut rng);
+        maze.set_cost(CircleCoord::create_with_arc_index(2, 5, &layout), 4);
+        maze.set_cost(CircleCoord::create_with_arc_index(3, 1, &layout), 9);
+
+        let serialized = MazeSerializer::serialize(&maze);
+        let deserialized = MazeDeserializer::deserialize(serialized).unwrap();
+        assert_eq!(maze.costs(), deserialized.costs());
+        assert_eq!(deserialized.cost(&CircleCoord::create_with_arc_index(1, 0, &layout)), 1);
+    }
+
+    #[test]
+    fn test_metadata_roundtrip() {
+        use crate::metadata::Metadata;
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        let mut rng = StdRng::seed_from_u64(42);
+        let mut maze = factory(4, &RingLayout::default(), &mut rng);
+        assert!(MazeSerializer::serialize(&maze).get("metadata").is_none());
+
+        let mut metadata = Metadata::generated("standard", 42, 1_792_314_061);
+        metadata.title = Some(String::from("Four rings"));
+        metadata.author = Some(String::from("A. Maze"));
+        metadata.set_solution(&maze.tree_diameter());
+        maze.set_metadata(metadata);
+
+        let serialized = MazeSerializer::serialize(&maze);
+        assert_eq!(serialized["metadata"]["created"], "2026-10-18T09:01:01Z");
+        let deserialized = MazeDeserializer::deserialize(serialized).unwrap();
+        assert_eq!(deserialized.metadata(), maze.metadata());
+    }
+
+    #[test]
+    fn test_factory_with_history_lists_every_inner_wall_once() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        let layout = RingLayout::default();
+        let mut rng = StdRng::seed_from_u64(42);
+        let (maze, order) = factory_with_history(6, &layout, &mut rng);
+
+        let distinct: HashSet<&Wall> = order.iter().collect();
+        assert_eq!(distinct.len(), order.len());
+        assert_eq!(order.len(), maze.arcs().len() + maze.lines().len() - layout.total_arcs(6));
+
+        for wall in &order {
+            match wall {
+                Wall::Arc(coord) => assert!(maze.arcs().contains(coord)),
+                Wall::Line(coord) => assert!(maze.lines().contains(coord)),
+            }
+        }
+
+        let mut same_seed = StdRng::seed_from_u64(42);
+        let plain = factory(6, &layout, &mut same_seed);
+        assert_eq!(plain.arcs(), maze.arcs());
+        assert_eq!(plain.lines(), maze.lines());
+    }
+
+    #[test]
+    fn test_generation_events_describe_the_walls() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        let layout = RingLayout::default();
+        let mut rng = StdRng::seed_from_u64(42);
+        let events: Vec<GenerationEvent> = generation_events(6, &layout, &mut rng).collect();
+        assert_eq!(serialize_generation_event(&events[0])["type"], Value::from("walk_started"));
+
+        assert!(matches!(events[0], GenerationEvent::WalkStarted(_)));
+        for pair in events.windows(2) {
+            if let GenerationEvent::CornerVisited(_) = pair[1] {
+                assert!(matches!(pair[0], GenerationEvent::WallAdded(_)));
+            }
+        }
+        assert!(events.iter().any(|e| matches!(e, GenerationEvent::LoopRejected(_))));
+
+        let added: Vec<Wall> = events
+            .iter()
+            .filter_map(|event| match event {
+                GenerationEvent::WallAdded(wall) => Some(wall.clone()),
+                _ => None,
+            })
+            .collect();
+
+        let mut same_seed = StdRng::seed_from_u64(42);
+        let (maze, order) = factory_with_history(6, &layout, &mut same_seed);
+        assert_eq!(added, order);
+        assert_eq!(maze.arcs().len() + maze.lines().len(), order.len() + layout.total_arcs(6));
+    }
+
+    #[test]
+    fn test_generation_can_be_finished_part_way() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        let layout = RingLayout::default();
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut generation = generation_events(5, &layout, &mut rng);
+        assert_eq!(generation.by_ref().take(10).count(), 10);
+        let (maze, _) = generation.finish();
+
+        let mut same_seed = StdRng::seed_from_u64(7);
+        let plain = factory(5, &layout, &mut same_seed);
+        assert_eq!(maze.arcs(), plain.arcs());
+        assert_eq!(maze.lines(), plain.lines());
+    }
+
+    #[test]
+    fn test_rotate_rings() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        let layout = RingLayout::default();
+        let mut rng = StdRng::seed_from_u64(42);
+        let maze = factory(5, &layout, &mut rng);
+
+        let full_turn: Vec<usize> = (0..5).map(|c| layout.total_arcs(c)).collect();
+        let turned = maze.rotate_rings(&full_turn);
+        assert_eq!(turned.arcs(), maze.arcs());
+        assert_eq!(turned.lines(), maze.lines());
+
+        let rotated = maze.rotate_rings(&[0, 0, 3]);
+        let back = rotated.rotate_rings(&[0, 0, layout.total_arcs(2) - 3]);
+        assert_ne!(rotated.lines(), maze.lines());
+        assert_eq!(back.arcs(), maze.arcs());
+        assert_eq!(back.lines(), maze.lines());
+    }
+
+    #[test]
+    fn test_wall_between() {
+        let layout = RingLayout::default();
+        let coord = |circle, arc| CircleCoord::create_with_arc_index(circle, arc, &layout);
+
+        assert_eq!(Wall::between(&coord(2, 3), &coord(2, 4)), Some(Wall::Line(coord(2, 4))));
+        assert_eq!(Wall::between(&coord(2, 4), &coord(2, 3)), Some(Wall::Line(coord(2, 4))));
+        assert_eq!(Wall::between(&coord(1, 2), &coord(2, 5)), Some(Wall::Arc(coord(2, 5))));
+        assert_eq!(Wall::between(&coord(1, 2), &coord(0, 0)), Some(Wall::Arc(coord(1, 2))));
+        assert_eq!(Wall::between(&coord(1, 2), &coord(2, 6)), None);
+        assert_eq!(Wall::between(&coord(2, 3), &coord(2, 5)), None);
+    }
 }
@@ -1,5 +1,6 @@
 use crate::circle_coord::CircleCoord;
 use crate::maze::Maze;
+use std::collections::BTreeSet;
 
 fn merge_coordinates<'a, I, F>(
     coordinates: I,
@@ -50,11 +51,18 @@ where
 }
 
 pub fn merge_lines(maze: &Maze) -> Vec<(CircleCoord, CircleCoord)> {
-    merge_coordinates(maze.lines(), |line| line.next_out(), false)
+    let tunnel_lines = maze.tunnel_lines();
+    // Merging depends on the order of the walls, so they are sorted for a stable result.
+    let lines: BTreeSet<&CircleCoord> =
+        maze.lines().iter().filter(|line| !tunnel_lines.contains(line)).collect();
+    merge_coordinates(lines, |line| line.next_out(), false)
 }
 
 pub fn merge_arcs(maze: &Maze) -> Vec<(CircleCoord, CircleCoord)> {
-    merge_coordinates(maze.arcs(), |arc| arc.next_clockwise(), true)
+    let tunnel_arcs = maze.tunnel_arcs();
+    let arcs: BTreeSet<&CircleCoord> =
+        maze.arcs().iter().filter(|arc| !tunnel_arcs.contains(arc)).collect();
+    merge_coordinates(arcs, |arc| arc.next_clockwise(), true)
 }
 
 #[cfg(test)]
@@ -0,0 +1,151 @@
+use crate::circle_coord::CircleCoord;
+use crate::maze::Maze;
+use crate::terrain::cheapest_route;
+
+/// The name and version of this program, recorded in every maze it generates.
+pub const GENERATOR_VERSION: &str =
+    concat!(env!("CARGO_PKG_NAME"), " ", env!("CARGO_PKG_VERSION"));
+
+/// Where a maze comes from and how it is meant to be played. Every part is optional; a maze
+/// without any of them saves no metadata at all.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Metadata {
+    pub title: Option<String>,
+    pub author: Option<String>,
+    /// When the maze was generated, as an RFC 3339 timestamp in UTC.
+    pub created: Option<String>,
+    /// The seed of the random generator. The same seed and options generate the same maze.
+    pub seed: Option<u64>,
+    /// The kind of maze the generator was asked for, such as `standard` or `weave`.
+    pub algorithm: Option<String>,
+    pub generator_version: Option<String>,
+    pub start: Option<CircleCoord>,
+    pub goal: Option<CircleCoord>,
+    /// A route from `start` to `goal`, saved so that it need not be searched for again.
+    pub solution: Option<Vec<CircleCoord>>,
+}
+
+impl Metadata {
+    /// The provenance of a maze generated now, `unix_seconds` after the epoch.
+    pub fn generated(algorithm: &str, seed: u64, unix_seconds: u64) -> Self {
+        Metadata {
+            created: Some(format_timestamp(unix_seconds)),
+            seed: Some(seed),
+            algorithm: Some(String::from(algorithm)),
+            generator_version: Some(String::from(GENERATOR_VERSION)),
+            ..Metadata::default()
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self == &Metadata::default()
+    }
+
+    /// Records the ends of `route` as start and goal and the route itself as the solution.
+    pub fn set_solution(&mut self, route: &[CircleCoord]) {
+        self.start = route.first().cloned();
+        self.goal = route.last().cloned();
+        self.solution = Some(route.to_vec());
+    }
+}
+
+/// The route the maze is meant to be solved along: its saved solution, else the cheapest route
+/// between its saved start and goal, else the cheapest route between the ends of its longest
+/// path.
+pub fn intended_route(maze: &Maze) -> Vec<CircleCoord> {
+    let metadata = maze.metadata();
+    if let Some(solution) = &metadata.solution {
+        return solution.clone();
+    }
+    if let (Some(start), Some(goal)) = (&metadata.start, &metadata.goal) {
+        if let Some((_, route)) = maze.cheapest_path(start, goal) {
+            return route;
+        }
+    }
+    if maze.costs().is_empty() {
+        maze.tree_diameter()
+    } else {
+        cheapest_route(maze)
+    }
+}
+
+// Formats seconds since the epoch as an RFC 3339 date and time in UTC. The date follows Howard
+// Hinnant's days-to-civil algorithm, which counts in 400-year eras starting on 1 March.
+fn format_timestamp(unix_seconds: u64) -> String {
+    let days = unix_seconds / 86_400;
+    let seconds_of_day = unix_seconds % 86_400;
+
+    let shifted = days + 719_468;
+    let era = shifted / 146_097;
+    let day_of_era = shifted % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        seconds_of_day / 3_600,
+        seconds_of_day % 3_600 / 60,
+        seconds_of_day % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(0), "1970-01-01T00:00:00Z");
+        assert_eq!(format_timestamp(951_782_400), "2000-02-29T00:00:00Z");
+        assert_eq!(format_timestamp(1_792_314_061), "2026-10-18T09:01:01Z");
+    }
+
+    #[test]
+    fn test_intended_route_prefers_the_saved_route() {
+        use crate::circle_coord::RingLayout;
+        use crate::maze::factory;
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        let mut rng = StdRng::seed_from_u64(42);
+        let mut maze = factory(5, &RingLayout::default(), &mut rng);
+        let diameter = maze.tree_diameter();
+        assert_eq!(intended_route(&maze), diameter);
+
+        let start = CircleCoord::create_with_arc_index(0, 0, maze.layout());
+        let goal = CircleCoord::create_with_arc_index(4, 3, maze.layout());
+        let metadata = Metadata {
+            start: Some(start.clone()),
+            goal: Some(goal.clone()),
+            ..Metadata::default()
+        };
+        maze.set_metadata(metadata);
+        let route = intended_route(&maze);
+        assert_eq!((&route[0], &route[route.len() - 1]), (&start, &goal));
+
+        let mut metadata = maze.metadata().clone();
+        metadata.set_solution(&route[..2]);
+        maze.set_metadata(metadata);
+        assert_eq!(intended_route(&maze), &route[..2]);
+    }
+
+    #[test]
+    fn test_generated_metadata_records_the_generator() {
+        let metadata = Metadata::generated("weave", 42, 0);
+
+        assert_eq!(metadata.seed, Some(42));
+        assert_eq!(metadata.algorithm.as_deref(), Some("weave"));
+        assert_eq!(metadata.generator_version.as_deref(), Some(GENERATOR_VERSION));
+        assert!(metadata.title.is_none());
+        assert!(!metadata.is_empty());
+        assert!(Metadata::default().is_empty());
+    }
+}
@@ -0,0 +1,96 @@
+use crate::circle_coord::{CircleCoord, RingLayout};
+use crate::maze::{factory, Maze, Wall};
+use rand::{seq::SliceRandom, Rng};
+use std::collections::HashSet;
+
+/// Generates a maze in which up to `count` passages can only be walked in one direction. The
+/// passages on the route between the ends of the longest path point towards its far end, so the
+/// goal stays reachable; elsewhere a one-way passage may lead into a branch with no way back.
+pub fn one_way_factory<R: Rng>(
+    circles: usize,
+    layout: &RingLayout,
+    count: usize,
+    rng: &mut R,
+) -> Maze {
+    let mut maze = factory(circles, layout, rng);
+    let route = maze.tree_diameter();
+
+    let mut passages = Vec::new();
+    let mut seen = HashSet::new();
+    for c in 0..circles {
+        for arc_index in 0..layout.total_arcs(c) {
+            let coord = CircleCoord::create_with_arc_index(c, arc_index, layout);
+            for neighbour in maze.accessible_neighbours(&coord) {
+                if let Some(wall) = Wall::between(&coord, &neighbour) {
+                    if seen.insert(wall) {
+                        passages.push((coord.clone(), neighbour));
+                    }
+                }
+            }
+        }
+    }
+    passages.shuffle(rng);
+
+    for (a, b) in passages.into_iter().take(count) {
+        let (from, to) = match route_direction(&route, &a, &b) {
+            Some(true) => (a, b),
+            Some(false) => (b, a),
+            None if rng.random_bool(0.5) => (a, b),
+            None => (b, a),
+        };
+        maze.add_one_way(from, to);
+    }
+
+    maze
+}
+
+// Whether the route walks the passage from `a` to `b`, or `None` if the passage is not on it.
+fn route_direction(route: &[CircleCoord], a: &CircleCoord, b: &CircleCoord) -> Option<bool> {
+    route.windows(2).find_map(|step| {
+        if &step[0] == a && &step[1] == b {
+            Some(true)
+        } else if &step[0] == b && &step[1] == a {
+            Some(false)
+        } else {
+            None
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_goal_stays_reachable() {
+        for seed in [42, 123, 456, 789] {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let plain = factory(8, &RingLayout::default(), &mut rng.clone());
+            let route = plain.tree_diameter();
+
+            let maze = one_way_factory(8, &RingLayout::default(), 40, &mut rng);
+            assert_eq!(maze.one_way().len(), 40);
+
+            let start = route.first().unwrap();
+            let goal = route.last().unwrap();
+            assert!(
+                maze.find_path(start, goal).is_some(),
+                "Seed {}: goal is not reachable",
+                seed
+            );
+        }
+    }
+
+    #[test]
+    fn test_one_way_passages_block_reverse_direction() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let maze = one_way_factory(6, &RingLayout::default(), 10, &mut rng);
+
+        for (from, to) in maze.one_way() {
+            assert!(maze.accessible_neighbours(from).contains(to));
+            assert!(!maze.accessible_neighbours(to).contains(from));
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use crate::circle_coord::{CircleCoord, RingLayout};
+use crate::error::MazeError;
+use crate::maze::{factory, Maze};
+use rand::{seq::IndexedRandom, Rng};
+use std::collections::HashSet;
+
+pub const MAX_PORTALS: usize = 8;
+
+/// Generates a maze with `pairs` teleporter pairs. The second cell of every pair is picked among
+/// the cells in the farther half of the maze, as seen from the first, so each portal is a real
+/// shortcut.
+pub fn portal_factory<R: Rng>(
+    circles: usize,
+    layout: &RingLayout,
+    pairs: usize,
+    rng: &mut R,
+) -> Result<Maze, MazeError> {
+    if pairs > MAX_PORTALS {
+        return Err(MazeError::options(format!(
+            "at most {} portal pairs are supported",
+            MAX_PORTALS
+        )));
+    }
+
+    let mut maze = factory(circles, layout, rng);
+    let mut used = HashSet::new();
+
+    let cells: Vec<CircleCoord> = (1..circles)
+        .flat_map(|c| {
+            (0..layout.total_arcs(c)).map(move |i| CircleCoord::create_with_arc_index(c, i, layout))
+        })
+        .collect();
+
+    for _ in 0..pairs {
+        let free: Vec<&CircleCoord> = cells.iter().filter(|c| !used.contains(*c)).collect();
+        let Some(&a) = free.choose(rng) else {
+            break;
+        };
+
+        let distances = maze.distances(a);
+        let farthest = free.iter().map(|c| distances[*c]).max().unwrap_or(0);
+        let distant: Vec<&CircleCoord> = free
+            .into_iter()
+            .filter(|c| *c != a && 2 * distances[*c] >= farthest)
+            .collect();
+        let Some(&b) = distant.choose(rng) else {
+            break;
+        };
+
+        used.insert(a.clone());
+        used.insert(b.clone());
+        maze.add_portal(a.clone(), b.clone());
+    }
+
+    Ok(maze)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_portals_connect_distant_cells() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let maze = portal_factory(8, &RingLayout::default(), 4, &mut rng).unwrap();
+        assert_eq!(maze.portals().len(), 4);
+
+        let cells: HashSet<&CircleCoord> = maze.portals().iter().flatten().collect();
+        assert_eq!(cells.len(), 8, "Every cell holds at most one portal");
+
+        for [a, b] in maze.portals() {
+            assert!(maze.accessible_neighbours(a).contains(b));
+            assert!(maze.accessible_neighbours(b).contains(a));
+            assert!(a.circle() > 0 && b.circle() > 0);
+        }
+    }
+
+    #[test]
+    fn test_portal_shortens_route() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let plain = factory(8, &RingLayout::default(), &mut rng.clone());
+        let maze = portal_factory(8, &RingLayout::default(), 1, &mut rng).unwrap();
+
+        let [a, b] = &maze.portals()[0];
+        let before = plain.distances(a)[b];
+        let farthest = plain.distances(a).into_values().max().unwrap();
+        assert!(2 * before >= farthest);
+        assert_eq!(maze.distances(a)[b], 1);
+    }
+
+    #[test]
+    fn test_portal_factory_rejects_too_many_pairs() {
+        let mut rng = StdRng::seed_from_u64(42);
+        assert!(portal_factory(8, &RingLayout::default(), MAX_PORTALS + 1, &mut rng).is_err());
+    }
+}
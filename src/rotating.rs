@@ -0,0 +1,164 @@
+use crate::circle_coord::{CircleCoord, RingLayout};
+use crate::error::MazeError;
+use crate::maze::{factory, Maze, Wall};
+use rand::Rng;
+use std::collections::HashSet;
+
+// Scrambles that happen to leave a route open are thrown away and rolled again.
+const MAX_SCRAMBLE_ATTEMPTS: usize = 100;
+
+/// A maze whose rings turn independently, like the rotating-ring toy. The outer boundary is the
+/// fixed frame with a single gap above `entrance`; the puzzle is solved once a route leads from
+/// there to the centre.
+#[derive(Debug)]
+pub struct RotatingPuzzle {
+    maze: Maze,
+    entrance: CircleCoord,
+}
+
+impl RotatingPuzzle {
+    pub fn maze(&self) -> &Maze {
+        &self.maze
+    }
+
+    pub fn entrance(&self) -> &CircleCoord {
+        &self.entrance
+    }
+
+    pub fn centre(&self) -> CircleCoord {
+        CircleCoord::create_with_arc_index(0, 0, self.maze.layout())
+    }
+
+    /// Finds clockwise turns for every ring, indexed by circle, that open a route from the
+    /// entrance to the centre, or `None` if no combination does.
+    pub fn solve(&self) -> Option<Vec<usize>> {
+        let mut offsets = vec![0; self.maze.circles()];
+        self.solve_from(self.maze.circles() - 1, &mut offsets)
+            .then_some(offsets)
+    }
+
+    // Turns the rings from the outside in. Every route to the centre first crosses into ring
+    // `circle - 1` from a cell of `circle` it reached without going further in, so a partial
+    // setting that leaves no such opening cannot be completed.
+    fn solve_from(&self, circle: usize, offsets: &mut [usize]) -> bool {
+        if circle == 0 {
+            return true;
+        }
+
+        for offset in 0..self.maze.layout().total_arcs(circle) {
+            offsets[circle] = offset;
+            let rotated = self.maze.rotate_rings(offsets);
+            let reached = reachable_cells(&rotated, &self.entrance, circle);
+
+            let has_opening = reached
+                .iter()
+                .any(|cell| cell.circle() == circle && !rotated.arcs().contains(cell));
+            if has_opening && self.solve_from(circle - 1, offsets) {
+                return true;
+            }
+        }
+
+        offsets[circle] = 0;
+        false
+    }
+
+    /// The route from the entrance to the centre once the rings are turned by `offsets`.
+    pub fn route(&self, offsets: &[usize]) -> Option<Vec<CircleCoord>> {
+        self.maze
+            .rotate_rings(offsets)
+            .find_path(&self.entrance, &self.centre())
+    }
+}
+
+// The cells reachable from `start` without entering rings inside `min_circle`.
+fn reachable_cells(maze: &Maze, start: &CircleCoord, min_circle: usize) -> HashSet<CircleCoord> {
+    let mut visited = HashSet::new();
+    let mut stack = vec![start.clone()];
+
+    while let Some(current) = stack.pop() {
+        if !visited.insert(current.clone()) {
+            continue;
+        }
+        for neighbour in maze.accessible_neighbours(&current) {
+            if neighbour.circle() >= min_circle {
+                stack.push(neighbour);
+            }
+        }
+    }
+
+    visited
+}
+
+/// Designs a rotating-ring puzzle. The entrance is cut above the outer cell farthest from the
+/// centre, so the unturned maze has the longest possible route, and then every ring is turned
+/// by a random number of arcs until no route is left.
+pub fn rotating_factory<R: Rng>(
+    circles: usize,
+    layout: &RingLayout,
+    rng: &mut R,
+) -> Result<RotatingPuzzle, MazeError> {
+    if circles < 2 {
+        return Err(MazeError::options("a rotating puzzle needs at least 2 circles"));
+    }
+
+    let mut design = factory(circles, layout, rng);
+    let centre = CircleCoord::create_with_arc_index(0, 0, layout);
+
+    let entrance = (0..layout.total_arcs(circles - 1))
+        .map(|arc_index| CircleCoord::create_with_arc_index(circles - 1, arc_index, layout))
+        .max_by_key(|cell| design.find_path(&centre, cell).map_or(0, |path| path.len()))
+        .expect("The outer ring has cells");
+    design.remove_wall(&Wall::Arc(entrance.next_out()));
+
+    for _ in 0..MAX_SCRAMBLE_ATTEMPTS {
+        let offsets: Vec<usize> = (0..circles)
+            .map(|c| if c == 0 { 0 } else { rng.random_range(0..layout.total_arcs(c)) })
+            .collect();
+        let maze = design.rotate_rings(&offsets);
+
+        if maze.find_path(&entrance, &centre).is_none() {
+            return Ok(RotatingPuzzle { maze, entrance });
+        }
+    }
+
+    Err(MazeError::options("failed to scramble the rings"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_scrambled_puzzle_is_closed_and_solvable() {
+        for seed in [42, 123, 456] {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let puzzle = rotating_factory(6, &RingLayout::default(), &mut rng).unwrap();
+
+            assert!(puzzle.route(&[0; 6]).is_none(), "Seed {}: scramble left a route", seed);
+
+            let offsets = puzzle.solve().expect("Puzzle should be solvable");
+            let route = puzzle.route(&offsets).expect("Solution should open a route");
+            assert_eq!(route.first(), Some(puzzle.entrance()));
+            assert_eq!(route.last(), Some(&puzzle.centre()));
+        }
+    }
+
+    #[test]
+    fn test_entrance_is_the_only_gap_in_the_frame() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let puzzle = rotating_factory(5, &RingLayout::default(), &mut rng).unwrap();
+        let layout = RingLayout::default();
+
+        let frame = puzzle.maze().arcs().iter().filter(|arc| arc.circle() == 5).count();
+        assert_eq!(frame, layout.total_arcs(5) - 1);
+        assert!(!puzzle.maze().arcs().contains(&puzzle.entrance().next_out()));
+    }
+
+    #[test]
+    fn test_rotating_factory_rejects_single_circle() {
+        let mut rng = StdRng::seed_from_u64(42);
+        assert!(rotating_factory(1, &RingLayout::default(), &mut rng).is_err());
+    }
+}
@@ -0,0 +1,261 @@
+use crate::circle_coord::{CircleCoord, RingLayout, Subdivision};
+use crate::error::MazeError;
+use crate::maze::{Maze, MazeDeserializer, MazeSerializer, Wall};
+use crate::validate::load_valid_maze;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+
+// Bumped whenever the byte layout below changes, so old codes are rejected instead of misread.
+const SHARE_CODE_VERSION: u8 = 1;
+
+const POWER_OF_TWO: u8 = 0;
+const WIDTH_RATIO: u8 = 1;
+
+const CHECKSUM_LEN: usize = 2;
+
+/// Every wall a maze of this size could have, ordered by ring and arc: first the arc on the
+/// inner side of each cell of the ring, then the line on its counter-clockwise side. The arcs
+/// of circle `circles` form the outer boundary, which has no lines.
+pub fn potential_walls(circles: usize, layout: &RingLayout) -> Vec<Wall> {
+    let mut walls = Vec::new();
+    for circle in 1..=circles {
+        let coords: Vec<CircleCoord> = (0..layout.total_arcs(circle))
+            .map(|arc_index| CircleCoord::create_with_arc_index(circle, arc_index, layout))
+            .collect();
+        walls.extend(coords.iter().cloned().map(Wall::Arc));
+        if circle < circles {
+            walls.extend(coords.into_iter().map(Wall::Line));
+        }
+    }
+    walls
+}
+
+/// Packs the walls of a maze into a URL-safe base64 code, for sharing it in a URL or QR code.
+/// The bytes are a version, the number of circles and the ring layout, one bit per potential
+/// wall and a CRC-16 of all of them. Only walls are stored, so mazes with more are refused.
+pub fn encode_share_code(maze: &Maze) -> Result<String, MazeError> {
+    if !maze.has_only_walls() {
+        return Err(MazeError::ShareCode(String::from(
+            "share codes only hold walls, not crossings, one-way passages, portals or costs",
+        )));
+    }
+
+    let mut bytes = vec![SHARE_CODE_VERSION];
+    write_varint(&mut bytes, maze.circles());
+    let layout = maze.layout();
+    write_varint(&mut bytes, layout.first_ring_arcs());
+    match layout.subdivision() {
+        Subdivision::PowerOfTwo => bytes.push(POWER_OF_TWO),
+        Subdivision::WidthRatio(ratio) => {
+            bytes.push(WIDTH_RATIO);
+            bytes.extend_from_slice(&ratio.to_le_bytes());
+        }
+    }
+
+    let walls = potential_walls(maze.circles(), layout);
+    let mut packed = vec![0u8; walls.len().div_ceil(8)];
+    for (i, wall) in walls.iter().enumerate() {
+        let present = match wall {
+            Wall::Arc(coord) => maze.arcs().contains(coord),
+            Wall::Line(coord) => maze.lines().contains(coord),
+        };
+        if present {
+            packed[i / 8] |= 0x80 >> (i % 8);
+        }
+    }
+    bytes.extend(packed);
+
+    bytes.extend_from_slice(&crc16(&bytes).to_be_bytes());
+    Ok(URL_SAFE_NO_PAD.encode(bytes))
+}
+
+/// Builds the maze a share code describes. A code can have a matching checksum and still
+/// describe an impossible maze, so the maze is checked like a loaded file.
+pub fn decode_share_code(code: &str) -> Result<Maze, MazeError> {
+    let bytes = URL_SAFE_NO_PAD
+        .decode(code.trim())
+        .map_err(|e| MazeError::ShareCode(format!("not a share code: {}", e)))?;
+    if bytes.len() < CHECKSUM_LEN + 1 {
+        return Err(MazeError::ShareCode(String::from("the code is too short")));
+    }
+    let (content, checksum) = bytes.split_at(bytes.len() - CHECKSUM_LEN);
+    if crc16(content).to_be_bytes() != checksum {
+        return Err(MazeError::ShareCode(String::from(
+            "the checksum does not match, the code may be mistyped or cut off",
+        )));
+    }
+
+    let mut reader = Reader { bytes: content, position: 0 };
+    let version = reader.byte()?;
+    if version != SHARE_CODE_VERSION {
+        return Err(MazeError::ShareCode(format!("unknown version {}", version)));
+    }
+    let circles = reader.varint()?;
+    let first_ring_arcs = reader.varint()?;
+    let subdivision = match reader.byte()? {
+        POWER_OF_TWO => Subdivision::PowerOfTwo,
+        WIDTH_RATIO => {
+            let ratio: [u8; 8] = reader.take(8)?.try_into().expect("Took exactly 8 bytes");
+            Subdivision::WidthRatio(f64::from_le_bytes(ratio))
+        }
+        other => return Err(MazeError::ShareCode(format!("unknown subdivision {}", other))),
+    };
+    let layout = RingLayout::new(first_ring_arcs, subdivision)
+        .map_err(|error| MazeError::ShareCode(error.to_string()))?;
+
+    // Every circle has at least `first_ring_arcs` arcs, so this rejects sizes the code cannot
+    // hold before listing their walls.
+    let packed = &content[reader.position..];
+    if circles == 0 || circles.saturating_mul(first_ring_arcs) > packed.len() * 8 {
+        return Err(MazeError::ShareCode(String::from("the walls do not match the maze size")));
+    }
+    let walls = potential_walls(circles, &layout);
+    if packed.len() != walls.len().div_ceil(8) {
+        return Err(MazeError::ShareCode(String::from("the walls do not match the maze size")));
+    }
+
+    let present = walls
+        .into_iter()
+        .enumerate()
+        .filter(|(i, _)| packed[i / 8] & (0x80 >> (i % 8)) != 0)
+        .map(|(_, wall)| wall)
+        .collect();
+    let maze = MazeDeserializer::from_walls(circles, &layout, present);
+    load_valid_maze(MazeSerializer::serialize(&maze))
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl Reader<'_> {
+    fn take(&mut self, count: usize) -> Result<&[u8], MazeError> {
+        let end = self.position + count;
+        let taken = self
+            .bytes
+            .get(self.position..end)
+            .ok_or(MazeError::ShareCode(String::from("the code is too short")))?;
+        self.position = end;
+        Ok(taken)
+    }
+
+    fn byte(&mut self) -> Result<u8, MazeError> {
+        Ok(self.take(1)?[0])
+    }
+
+    // Seven bits per byte, least significant first, with the high bit set on all but the last.
+    fn varint(&mut self) -> Result<usize, MazeError> {
+        let mut value: usize = 0;
+        for shift in (0..usize::BITS).step_by(7) {
+            let byte = self.byte()?;
+            value |= usize::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(MazeError::ShareCode(String::from("a number is too large")))
+    }
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut value: usize) {
+    while value >= 0x80 {
+        bytes.push((value & 0x7f) as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+// CRC-16/CCITT-FALSE, which catches every single mistyped character.
+fn crc16(bytes: &[u8]) -> u16 {
+    let mut crc: u16 = 0xffff;
+    for byte in bytes {
+        crc ^= u16::from(*byte) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 { (crc << 1) ^ 0x1021 } else { crc << 1 };
+        }
+    }
+    crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::DiagnosticKind;
+    use crate::maze::factory;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_potential_walls_cover_every_ring() {
+        let layout = RingLayout::default();
+        // Rings 1 to 3 have 6, 12 and 12 cells; the boundary at circle 3 has 12 arcs.
+        assert_eq!(potential_walls(3, &layout).len(), (6 + 6) + (12 + 12) + 12);
+    }
+
+    #[test]
+    fn test_share_code_round_trips() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let layout = RingLayout::new(5, Subdivision::WidthRatio(1.5)).unwrap();
+        let maze = factory(10, &layout, &mut rng);
+
+        let code = encode_share_code(&maze).unwrap();
+        assert!(code.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'));
+
+        let decoded = decode_share_code(&code).unwrap();
+        assert_eq!(decoded.circles(), maze.circles());
+        assert_eq!(decoded.layout(), maze.layout());
+        assert_eq!(decoded.arcs(), maze.arcs());
+        assert_eq!(decoded.lines(), maze.lines());
+    }
+
+    #[test]
+    fn test_share_code_is_much_smaller_than_json() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let maze = factory(20, &RingLayout::default(), &mut rng);
+
+        let code = encode_share_code(&maze).unwrap();
+        let json = serde_json::to_string_pretty(&MazeSerializer::serialize(&maze)).unwrap();
+        assert!(code.len() < 400, "{} characters", code.len());
+        assert!(code.len() * 50 < json.len());
+    }
+
+    #[test]
+    fn test_mistyped_codes_are_rejected() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let maze = factory(4, &RingLayout::default(), &mut rng);
+        let code = encode_share_code(&maze).unwrap();
+
+        let mut mistyped: Vec<char> = code.chars().collect();
+        mistyped[5] = if mistyped[5] == 'A' { 'B' } else { 'A' };
+        let mistyped: String = mistyped.into_iter().collect();
+
+        for bad in [mistyped.as_str(), &code[..code.len() - 3], "", "not a code!"] {
+            let error = decode_share_code(bad).unwrap_err();
+            assert_eq!(error.name(), "ShareCodeError", "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_codes_of_impossible_mazes_are_rejected() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut maze = factory(4, &RingLayout::default(), &mut rng);
+        // A gap in the outer boundary, packed with a checksum that matches.
+        maze.remove_wall(&Wall::Arc(CircleCoord::create_with_arc_index(4, 0, maze.layout())));
+        let code = encode_share_code(&maze).unwrap();
+
+        let MazeError::Semantic(diagnostics) = decode_share_code(&code).unwrap_err() else {
+            panic!("Expected a semantic error");
+        };
+        assert!(diagnostics.iter().any(|d| d.kind() == DiagnosticKind::MissingBoundary));
+    }
+
+    #[test]
+    fn test_mazes_with_more_than_walls_have_no_share_code() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut maze = factory(4, &RingLayout::default(), &mut rng);
+        maze.set_cost(CircleCoord::create_with_arc_index(1, 0, maze.layout()), 3);
+
+        assert_eq!(encode_share_code(&maze).unwrap_err().name(), "ShareCodeError");
+    }
+}
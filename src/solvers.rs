@@ -0,0 +1,508 @@
+use crate::circle_coord::CircleCoord;
+use crate::error::MazeError;
+use crate::maze::Maze;
+use serde_json::{json, Value};
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+// The bearing of every exit towards the centre, which is also where a walk without a previous
+// cell starts turning from.
+const INWARD: (usize, usize) = (2, 0);
+
+/// The classic maze-solving strategies, run so that every step they take can be watched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Solver {
+    BreadthFirst,
+    DepthFirst,
+    LeftHand,
+    RightHand,
+    DeadEndFilling,
+    Tremaux,
+}
+
+impl Solver {
+    pub fn from_name(name: &str) -> Result<Self, MazeError> {
+        match name {
+            "bfs" => Ok(Solver::BreadthFirst),
+            "dfs" => Ok(Solver::DepthFirst),
+            "left-hand" => Ok(Solver::LeftHand),
+            "right-hand" => Ok(Solver::RightHand),
+            "dead-end" => Ok(Solver::DeadEndFilling),
+            "tremaux" => Ok(Solver::Tremaux),
+            other => Err(MazeError::options(format!("Unknown solver '{}'", other))),
+        }
+    }
+}
+
+/// A single step of a solver, in the order the solver took it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SolverEvent {
+    /// The solver reached a cell.
+    Visited(CircleCoord),
+    /// The solver gave up on a cell and walked back out of it.
+    Backtracked(CircleCoord),
+    /// Dead-end filling ruled a cell out without entering it.
+    Filled(CircleCoord),
+}
+
+/// Everything a solver did on its way from the start to the goal, and the route it found, if any.
+#[derive(Debug)]
+pub struct SolverRun {
+    events: Vec<SolverEvent>,
+    path: Option<Vec<CircleCoord>>,
+}
+
+impl SolverRun {
+    pub fn events(&self) -> &[SolverEvent] {
+        &self.events
+    }
+
+    pub fn path(&self) -> Option<&[CircleCoord]> {
+        self.path.as_deref()
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Hand {
+    Left,
+    Right,
+}
+
+/// Runs `solver` from `start` to `goal`, recording the order in which it explores the cells.
+pub fn run_solver(
+    maze: &Maze,
+    solver: Solver,
+    start: &CircleCoord,
+    goal: &CircleCoord,
+) -> SolverRun {
+    match solver {
+        Solver::BreadthFirst => breadth_first(maze, start, goal),
+        Solver::DepthFirst => depth_first(maze, start, goal),
+        Solver::LeftHand => wall_follower(maze, start, goal, Hand::Left),
+        Solver::RightHand => wall_follower(maze, start, goal, Hand::Right),
+        Solver::DeadEndFilling => dead_end_filling(maze, start, goal),
+        Solver::Tremaux => tremaux(maze, start, goal),
+    }
+}
+
+fn breadth_first(maze: &Maze, start: &CircleCoord, goal: &CircleCoord) -> SolverRun {
+    let mut events = Vec::new();
+    let mut parents = HashMap::from([(start.clone(), None)]);
+    let mut queue = VecDeque::from([start.clone()]);
+
+    while let Some(current) = queue.pop_front() {
+        events.push(SolverEvent::Visited(current.clone()));
+        if &current == goal {
+            return SolverRun { events, path: Some(trace_back(&parents, goal)) };
+        }
+
+        for neighbour in maze.accessible_neighbours(&current) {
+            if let Entry::Vacant(entry) = parents.entry(neighbour.clone()) {
+                entry.insert(Some(current.clone()));
+                queue.push_back(neighbour);
+            }
+        }
+    }
+
+    SolverRun { events, path: None }
+}
+
+fn trace_back(
+    parents: &HashMap<CircleCoord, Option<CircleCoord>>,
+    goal: &CircleCoord,
+) -> Vec<CircleCoord> {
+    let mut path = vec![goal.clone()];
+    while let Some(Some(parent)) = parents.get(path.last().expect("Path is never empty")) {
+        path.push(parent.clone());
+    }
+    path.reverse();
+    path
+}
+
+fn depth_first(maze: &Maze, start: &CircleCoord, goal: &CircleCoord) -> SolverRun {
+    let mut events = vec![SolverEvent::Visited(start.clone())];
+    let mut visited = HashSet::from([start.clone()]);
+    let mut stack = vec![start.clone()];
+
+    while let Some(current) = stack.last().cloned() {
+        if &current == goal {
+            return SolverRun { events, path: Some(stack) };
+        }
+
+        let next = maze
+            .accessible_neighbours(&current)
+            .into_iter()
+            .find(|neighbour| !visited.contains(neighbour));
+        match next {
+            Some(next) => {
+                visited.insert(next.clone());
+                events.push(SolverEvent::Visited(next.clone()));
+                stack.push(next);
+            }
+            None => {
+                events.push(SolverEvent::Backtracked(current));
+                stack.pop();
+            }
+        }
+    }
+
+    SolverRun { events, path: None }
+}
+
+// Keeps one hand on the wall. The walk gives up once it repeats a move, since from then on it
+// only circles around a part of the wall that does not touch the goal.
+fn wall_follower(maze: &Maze, start: &CircleCoord, goal: &CircleCoord, hand: Hand) -> SolverRun {
+    let mut events = vec![SolverEvent::Visited(start.clone())];
+    let mut route = vec![start.clone()];
+    let mut moves = HashSet::new();
+    let mut current = start.clone();
+    let mut previous: Option<CircleCoord> = None;
+
+    while &current != goal {
+        let Some(next) = turn_by_hand(maze, &current, previous.as_ref(), hand) else {
+            return SolverRun { events, path: None };
+        };
+        if !moves.insert((current.clone(), next.clone())) {
+            return SolverRun { events, path: None };
+        }
+
+        step_route(&mut route, next.clone(), &mut events);
+        previous = Some(current);
+        current = next;
+    }
+
+    SolverRun { events, path: Some(route) }
+}
+
+// The first exit met when turning from the way back towards `hand`. The way back is only taken
+// when there is no other exit. A portal is not on the wall the hand follows, so it is passed by.
+fn turn_by_hand(
+    maze: &Maze,
+    current: &CircleCoord,
+    previous: Option<&CircleCoord>,
+    hand: Hand,
+) -> Option<CircleCoord> {
+    let through_portal = |exit: &CircleCoord| {
+        maze.portals()
+            .iter()
+            .any(|[a, b]| (a == current && b == exit) || (a == exit && b == current))
+    };
+    let mut exits: Vec<CircleCoord> = maze
+        .accessible_neighbours(current)
+        .into_iter()
+        .filter(|exit| !through_portal(exit))
+        .collect();
+    exits.sort_by_key(|exit| exit_bearing(current, exit));
+    let back = match previous {
+        Some(previous) => exit_bearing(current, previous),
+        None => INWARD,
+    };
+
+    let next = match hand {
+        Hand::Left => {
+            let split = exits.partition_point(|exit| exit_bearing(current, exit) <= back);
+            exits[split..].iter().chain(&exits[..split]).next()
+        }
+        Hand::Right => {
+            let split = exits.partition_point(|exit| exit_bearing(current, exit) < back);
+            exits[..split].iter().rev().chain(exits[split..].iter().rev()).next()
+        }
+    };
+    next.cloned()
+}
+
+// Where an exit lies when walking clockwise around the edge of `cell`, starting from the
+// counter-clockwise end of its outer side: outwards, clockwise, inwards, counter-clockwise.
+fn exit_bearing(cell: &CircleCoord, exit: &CircleCoord) -> (usize, usize) {
+    if exit.circle() > cell.circle() {
+        return (0, exit.arc_index());
+    }
+    if exit.circle() < cell.circle() {
+        return INWARD;
+    }
+
+    let total_arcs = cell.layout().total_arcs(cell.circle());
+    let steps = (exit.arc_index() + total_arcs - cell.arc_index()) % total_arcs;
+    if steps <= total_arcs / 2 {
+        (1, 0)
+    } else {
+        (3, 0)
+    }
+}
+
+// Moves the end of a walked route to `next`, erasing the loop if the walk returns to a cell
+// already on the route.
+fn step_route(route: &mut Vec<CircleCoord>, next: CircleCoord, events: &mut Vec<SolverEvent>) {
+    if let Some(position) = route.iter().position(|cell| cell == &next) {
+        events.extend(route.drain(position + 1..).rev().map(SolverEvent::Backtracked));
+    } else {
+        events.push(SolverEvent::Visited(next.clone()));
+        route.push(next);
+    }
+}
+
+// Fills dead ends until only the cells between the start and the goal are left open.
+fn dead_end_filling(maze: &Maze, start: &CircleCoord, goal: &CircleCoord) -> SolverRun {
+    let mut events = Vec::new();
+    let mut filled = HashSet::new();
+
+    let is_dead_end = |cell: &CircleCoord, filled: &HashSet<CircleCoord>| {
+        cell != start
+            && cell != goal
+            && !filled.contains(cell)
+            && maze
+                .accessible_neighbours(cell)
+                .iter()
+                .filter(|neighbour| !filled.contains(*neighbour))
+                .count()
+                <= 1
+    };
+
+    let layout = maze.layout();
+    let mut queue: VecDeque<CircleCoord> = (0..maze.circles())
+        .flat_map(|c| {
+            (0..layout.total_arcs(c))
+                .map(move |arc_index| CircleCoord::create_with_arc_index(c, arc_index, layout))
+        })
+        .filter(|cell| is_dead_end(cell, &filled))
+        .collect();
+
+    while let Some(cell) = queue.pop_front() {
+        if !is_dead_end(&cell, &filled) {
+            continue;
+        }
+        filled.insert(cell.clone());
+        events.push(SolverEvent::Filled(cell.clone()));
+
+        for neighbour in maze.accessible_neighbours(&cell) {
+            if is_dead_end(&neighbour, &filled) {
+                queue.push_back(neighbour);
+            }
+        }
+    }
+
+    let mut parents = HashMap::from([(start.clone(), None)]);
+    let mut queue = VecDeque::from([start.clone()]);
+    while let Some(current) = queue.pop_front() {
+        if &current == goal {
+            return SolverRun { events, path: Some(trace_back(&parents, goal)) };
+        }
+        for neighbour in maze.accessible_neighbours(&current) {
+            if filled.contains(&neighbour) {
+                continue;
+            }
+            if let Entry::Vacant(entry) = parents.entry(neighbour.clone()) {
+                entry.insert(Some(current.clone()));
+                queue.push_back(neighbour);
+            }
+        }
+    }
+
+    SolverRun { events, path: None }
+}
+
+// Marks every passage each time it is walked and never walks one a third time. Reaching a
+// junction that was seen before through a new passage means a loop, so the walk turns back.
+fn tremaux(maze: &Maze, start: &CircleCoord, goal: &CircleCoord) -> SolverRun {
+    let mut events = vec![SolverEvent::Visited(start.clone())];
+    let mut route = vec![start.clone()];
+    let mut marks: HashMap<(CircleCoord, CircleCoord), usize> = HashMap::new();
+    let mut current = start.clone();
+    let mut previous: Option<CircleCoord> = None;
+
+    while &current != goal {
+        let exits = maze.accessible_neighbours(&current);
+        let marks_on = |exit: &CircleCoord| {
+            marks.get(&passage(&current, exit)).copied().unwrap_or(0)
+        };
+
+        let closes_loop = previous.as_ref().is_some_and(|previous| {
+            marks_on(previous) == 1
+                && exits.contains(previous)
+                && exits.iter().any(|exit| exit != previous && marks_on(exit) > 0)
+        });
+        let next = if closes_loop {
+            previous.clone()
+        } else {
+            exits
+                .iter()
+                .filter(|exit| marks_on(exit) < 2)
+                .min_by_key(|exit| marks_on(exit))
+                .cloned()
+        };
+        let Some(next) = next else {
+            return SolverRun { events, path: None };
+        };
+
+        *marks.entry(passage(&current, &next)).or_insert(0) += 1;
+        step_route(&mut route, next.clone(), &mut events);
+        previous = Some(current);
+        current = next;
+    }
+
+    SolverRun { events, path: Some(route) }
+}
+
+// The passage between two cells, whichever way it is walked.
+fn passage(a: &CircleCoord, b: &CircleCoord) -> (CircleCoord, CircleCoord) {
+    if (a.circle(), a.arc_index()) <= (b.circle(), b.arc_index()) {
+        (a.clone(), b.clone())
+    } else {
+        (b.clone(), a.clone())
+    }
+}
+
+/// A solver step as the JSON object `--solve-events` prints and `solve_maze_events` lists.
+pub fn serialize_solver_event(event: &SolverEvent) -> Value {
+    let (kind, coord) = match event {
+        SolverEvent::Visited(coord) => ("visited", coord),
+        SolverEvent::Backtracked(coord) => ("backtracked", coord),
+        SolverEvent::Filled(coord) => ("filled", coord),
+    };
+    json!({ "type": kind, "circle": coord.circle(), "arc": coord.arc_index() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circle_coord::RingLayout;
+    use crate::maze::{factory, Wall};
+    use crate::terrain::terrain_factory;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    const SOLVERS: [Solver; 6] = [
+        Solver::BreadthFirst,
+        Solver::DepthFirst,
+        Solver::LeftHand,
+        Solver::RightHand,
+        Solver::DeadEndFilling,
+        Solver::Tremaux,
+    ];
+
+    fn assert_walkable(maze: &Maze, path: &[CircleCoord]) {
+        for step in path.windows(2) {
+            assert!(maze.accessible_neighbours(&step[0]).contains(&step[1]));
+        }
+    }
+
+    #[test]
+    fn test_every_solver_finds_the_only_route() {
+        for seed in [42, 123, 456] {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let maze = factory(7, &RingLayout::default(), &mut rng);
+            let route = maze.tree_diameter();
+            let (start, goal) = (route.first().unwrap(), route.last().unwrap());
+
+            for solver in SOLVERS {
+                let run = run_solver(&maze, solver, start, goal);
+                assert_eq!(run.path(), Some(route.as_slice()), "Seed {}: {:?}", seed, solver);
+                assert!(!run.events().is_empty());
+            }
+        }
+    }
+
+    #[test]
+    fn test_breadth_first_visits_cells_by_distance() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let maze = factory(6, &RingLayout::default(), &mut rng);
+        let start = CircleCoord::create_with_arc_index(0, 0, maze.layout());
+        let goal = maze.tree_diameter()[0].clone();
+        let distances = maze.distances(&start);
+
+        let run = run_solver(&maze, Solver::BreadthFirst, &start, &goal);
+        let order: Vec<usize> = run
+            .events()
+            .iter()
+            .map(|event| match event {
+                SolverEvent::Visited(cell) => distances[cell],
+                other => panic!("Unexpected event {:?}", other),
+            })
+            .collect();
+        assert!(order.windows(2).all(|w| w[0] <= w[1]));
+    }
+
+    #[test]
+    fn test_walkers_backtrack_out_of_every_wrong_cell() {
+        let mut rng = StdRng::seed_from_u64(99);
+        let maze = factory(6, &RingLayout::default(), &mut rng);
+        let route = maze.tree_diameter();
+        let (start, goal) = (route.first().unwrap(), route.last().unwrap());
+
+        for solver in [Solver::DepthFirst, Solver::LeftHand, Solver::RightHand, Solver::Tremaux] {
+            let run = run_solver(&maze, solver, start, goal);
+            let mut open = HashSet::new();
+            for event in run.events() {
+                match event {
+                    SolverEvent::Visited(cell) => assert!(open.insert(cell.clone())),
+                    SolverEvent::Backtracked(cell) => assert!(open.remove(cell)),
+                    SolverEvent::Filled(_) => panic!("{:?} does not fill", solver),
+                }
+            }
+            assert_eq!(open, route.iter().cloned().collect(), "{:?}", solver);
+        }
+    }
+
+    #[test]
+    fn test_left_and_right_hands_explore_in_opposite_order() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let maze = factory(6, &RingLayout::default(), &mut rng);
+        let route = maze.tree_diameter();
+        let (start, goal) = (route.first().unwrap(), route.last().unwrap());
+
+        let left = run_solver(&maze, Solver::LeftHand, start, goal);
+        let right = run_solver(&maze, Solver::RightHand, start, goal);
+        assert_ne!(left.events(), right.events());
+    }
+
+    #[test]
+    fn test_solvers_handle_loops() {
+        for seed in [1, 2, 3] {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let mut maze = terrain_factory(6, &RingLayout::default(), 4, &mut rng);
+            let layout = *maze.layout();
+            maze.remove_wall(&Wall::Line(CircleCoord::create_with_arc_index(3, 5, &layout)));
+            let start = CircleCoord::create_with_arc_index(0, 0, &layout);
+            let goal = CircleCoord::create_with_arc_index(5, layout.total_arcs(5) / 2, &layout);
+
+            for solver in [Solver::BreadthFirst, Solver::DepthFirst, Solver::Tremaux] {
+                let run = run_solver(&maze, solver, &start, &goal);
+                let path = run.path().expect("Goal is reachable");
+                assert_eq!(path.first(), Some(&start));
+                assert_eq!(path.last(), Some(&goal));
+                assert_walkable(&maze, path);
+            }
+        }
+    }
+
+    #[test]
+    fn test_wall_followers_pass_portals_by() {
+        use crate::portals::portal_factory;
+
+        let mut rng = StdRng::seed_from_u64(42);
+        let maze = portal_factory(8, &RingLayout::default(), 3, &mut rng).unwrap();
+        let route = maze.tree_diameter();
+        let (start, goal) = (&route[0], &route[route.len() - 1]);
+
+        for solver in [Solver::LeftHand, Solver::RightHand] {
+            let path = run_solver(&maze, solver, start, goal).path().unwrap().to_vec();
+            assert!(path.windows(2).all(|step| Wall::between(&step[0], &step[1]).is_some()));
+        }
+    }
+
+    #[test]
+    fn test_from_name() {
+        assert_eq!(Solver::from_name("tremaux"), Ok(Solver::Tremaux));
+        assert_eq!(Solver::from_name("right-hand"), Ok(Solver::RightHand));
+        assert!(Solver::from_name("astar").is_err());
+    }
+
+    #[test]
+    fn test_serialize_solver_event() {
+        let coord = CircleCoord::create_with_arc_index(2, 5, &RingLayout::default());
+        assert_eq!(
+            serialize_solver_event(&SolverEvent::Backtracked(coord)),
+            json!({ "type": "backtracked", "circle": 2, "arc": 5 })
+        );
+    }
+}
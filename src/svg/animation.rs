@@ -0,0 +1,30 @@
+use crate::maze::Wall;
+
+use super::borders::render_wall;
+
+// Wraps an element so that it is hidden until `begin` seconds into the animation.
+pub fn render_appearing(element: &str, begin: f64) -> String {
+    format!(
+        r#"  <g opacity="0">
+    <set attributeName="opacity" to="1" begin="{:.3}s" fill="freeze"/>
+  {}  </g>
+"#,
+        begin, element
+    )
+}
+
+/// Every wall appears in the order it was placed, spread evenly over `duration` seconds.
+pub fn render_wall_animation(order: &[Wall], duration: f64) -> String {
+    let step = duration / order.len().max(1) as f64;
+
+    let mut content = String::new();
+    content.push_str(
+        r#"<g id="generation" fill="none" stroke="black" stroke-width="1" stroke-linecap="round">
+"#,
+    );
+    for (i, wall) in order.iter().enumerate() {
+        content.push_str(&render_appearing(&render_wall(wall), i as f64 * step));
+    }
+    content.push_str("</g>\n");
+    content
+}
@@ -1,11 +1,34 @@
-use crate::maze::Maze;
+use crate::maze::{Maze, Wall};
 use crate::merge::{merge_arcs, merge_lines};
+use fraction::Fraction;
 
 use super::geometry::{
     calc_large_arc_flag, create_svg_arc_path, fraction_to_degrees, normalize_angle_diff,
-    polar_to_cartesian, CIRCLE_RADIUS_STEP,
+    polar_to_cartesian, CIRCLE_RADIUS_STEP, DEGREES_IN_CIRCLE,
 };
 
+// Draws a single wall on its own, without merging it with its neighbours.
+pub fn render_wall(wall: &Wall) -> String {
+    match wall {
+        Wall::Arc(coord) => {
+            let radius = coord.circle() * CIRCLE_RADIUS_STEP;
+            let total_arcs = coord.layout().total_arcs(coord.circle());
+            let end_angle = coord.angle()
+                + Fraction::from(DEGREES_IN_CIRCLE as u64) / Fraction::from(total_arcs);
+            create_svg_arc_path(radius, coord.angle(), &end_angle, 1, 0)
+        }
+        Wall::Line(coord) => {
+            let start = polar_to_cartesian(coord.circle() * CIRCLE_RADIUS_STEP, coord.angle());
+            let end = polar_to_cartesian((coord.circle() + 1) * CIRCLE_RADIUS_STEP, coord.angle());
+            format!(
+                r#"  <line x1="{:.8}" y1="{:.8}" x2="{:.8}" y2="{:.8}"/>
+"#,
+                start.x, start.y, end.x, end.y
+            )
+        }
+    }
+}
+
 fn render_arcs(maze: &Maze) -> String {
     let mut content = String::new();
     let merged_arcs = merge_arcs(maze);
@@ -65,14 +88,24 @@ fn render_lines(maze: &Maze) -> String {
     content
 }
 
-pub fn render_borders(maze: &Maze) -> String {
+pub fn render_border_elements(maze: &Maze) -> String {
+    let mut content = String::new();
+    content.push_str(&render_arcs(maze));
+    content.push_str(&render_lines(maze));
+    content
+}
+
+pub fn render_borders_group(elements: &str) -> String {
     let mut content = String::new();
     content.push_str(
         r#"<g id="borders" fill="none" stroke="black" stroke-width="1" stroke-linecap="round">
 "#,
     );
-    content.push_str(&render_arcs(maze));
-    content.push_str(&render_lines(maze));
+    content.push_str(elements);
     content.push_str("</g>\n");
     content
 }
+
+pub fn render_borders(maze: &Maze) -> String {
+    render_borders_group(&render_border_elements(maze))
+}
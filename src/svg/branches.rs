@@ -0,0 +1,70 @@
+use crate::branches::Branch;
+use crate::circle_coord::CircleCoord;
+use crate::error::MazeError;
+
+use super::geometry::{calc_display_angle, calc_display_radius, polar_to_cartesian};
+use super::heatmap::Palette;
+
+const BRANCH_COLORS: [&str; 8] = [
+    "#a6cee3", "#b2df8a", "#fb9a99", "#fdbf6f", "#cab2d6", "#ffff99", "#8dd3c7", "#fccde5",
+];
+const DECISION_POINT_RADIUS: f64 = 1.5;
+
+/// How the cells of dead-end branches are coloured.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BranchColoring {
+    /// Along the palette by the distance from the solution path, relative to the deepest branch.
+    Depth(Palette),
+    /// One colour per branch.
+    PerBranch,
+}
+
+impl BranchColoring {
+    /// `depth` colours by depth along the `heat` palette, `branch` gives every branch its own
+    /// colour.
+    pub fn from_name(name: &str) -> Result<Self, MazeError> {
+        match name {
+            "depth" => Ok(BranchColoring::Depth(Palette::from_name("heat")?)),
+            "branch" => Ok(BranchColoring::PerBranch),
+            other => Err(MazeError::options(format!("Unknown branch colouring '{}'", other))),
+        }
+    }
+}
+
+pub fn calc_branch_fills(
+    branches: &[Branch],
+    coloring: &BranchColoring,
+) -> Vec<(CircleCoord, String)> {
+    let deepest = branches.iter().map(|branch| branch.depth()).max().unwrap_or(1);
+
+    let mut fills = Vec::new();
+    for (i, branch) in branches.iter().enumerate() {
+        for (coord, depth) in branch.cells() {
+            let color = match coloring {
+                BranchColoring::Depth(palette) => palette.color_at(*depth as f64 / deepest as f64),
+                BranchColoring::PerBranch => BRANCH_COLORS[i % BRANCH_COLORS.len()].to_string(),
+            };
+            fills.push((coord.clone(), color));
+        }
+    }
+    fills
+}
+
+pub fn render_decision_points(points: &[CircleCoord]) -> String {
+    let mut content = String::new();
+    content.push_str(
+        r#"<g id="decision-points" fill="white" stroke="purple" stroke-width="0.8">
+"#,
+    );
+    for coord in points {
+        let radius = calc_display_radius(coord.circle());
+        let point = polar_to_cartesian(radius, &calc_display_angle(coord));
+        content.push_str(&format!(
+            r#"  <circle cx="{:.8}" cy="{:.8}" r="{}"/>
+"#,
+            point.x, point.y, DECISION_POINT_RADIUS
+        ));
+    }
+    content.push_str("</g>\n");
+    content
+}
@@ -0,0 +1,54 @@
+use crate::circle_coord::CircleCoord;
+
+use super::geometry::{calc_cell_area, polar_to_cartesian, CellArea};
+
+pub fn render_cell(coord: &CircleCoord, fill: &str, opacity: f64) -> String {
+    match calc_cell_area(coord) {
+        CellArea::Disc { radius } => format!(
+            r#"  <circle r="{}" fill="{}" fill-opacity="{:.3}"/>
+"#,
+            radius, fill, opacity
+        ),
+        CellArea::Sector { inner_radius, outer_radius, start_angle, end_angle } => {
+            let outer_start = polar_to_cartesian(outer_radius, &start_angle);
+            let outer_end = polar_to_cartesian(outer_radius, &end_angle);
+            let inner_end = polar_to_cartesian(inner_radius, &end_angle);
+            let inner_start = polar_to_cartesian(inner_radius, &start_angle);
+
+            format!(
+                r#"  <path d="M {:.8},{:.8} A {},{} 0 0 1 {:.8},{:.8} L {:.8},{:.8} A {},{} 0 0 0 {:.8},{:.8} Z" fill="{}" fill-opacity="{:.3}"/>
+"#,
+                outer_start.x,
+                outer_start.y,
+                outer_radius,
+                outer_radius,
+                outer_end.x,
+                outer_end.y,
+                inner_end.x,
+                inner_end.y,
+                inner_radius,
+                inner_radius,
+                inner_start.x,
+                inner_start.y,
+                fill,
+                opacity
+            )
+        }
+    }
+}
+
+/// Fills every listed cell with its colour, in a group with the given id. Later entries are
+/// drawn on top of earlier ones.
+pub fn render_cell_fills(id: &str, fills: &[(CircleCoord, String)]) -> String {
+    let mut content = String::new();
+    content.push_str(&format!(
+        r#"<g id="{}" stroke="none">
+"#,
+        id
+    ));
+    for (coord, color) in fills {
+        content.push_str(&render_cell(coord, color, 1.0));
+    }
+    content.push_str("</g>\n");
+    content
+}
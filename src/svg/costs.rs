@@ -0,0 +1,24 @@
+use crate::maze::Maze;
+use std::collections::BTreeMap;
+
+use super::cells::render_cell;
+
+const COST_COLOR: &str = "saddlebrown";
+const MAX_COST_OPACITY: f64 = 0.6;
+
+// Shades every cell with a cost above 1, the dearest cells darkest.
+pub fn render_costs(maze: &Maze) -> String {
+    let max_cost = maze.costs().values().copied().max().unwrap_or(1);
+
+    let mut content = String::new();
+    content.push_str(
+        r#"<g id="costs" stroke="none">
+"#,
+    );
+    for (coord, cost) in BTreeMap::from_iter(maze.costs()) {
+        let opacity = MAX_COST_OPACITY * f64::from(*cost) / f64::from(max_cost);
+        content.push_str(&render_cell(coord, COST_COLOR, opacity));
+    }
+    content.push_str("</g>\n");
+    content
+}
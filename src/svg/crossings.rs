@@ -0,0 +1,83 @@
+use crate::circle_coord::CircleCoord;
+use crate::maze::{Maze, TunnelAxis};
+use fraction::Fraction;
+use std::collections::BTreeMap;
+
+use super::geometry::{
+    create_svg_arc_path, polar_to_cartesian, CIRCLE_RADIUS_STEP, DEGREES_IN_CIRCLE,
+};
+
+// Distance between the walls of the passage over a crossing and the edge of the crossing cell.
+const CROSSING_INSET: usize = 2;
+const CROSSING_ANGLE_INSET_DIVISOR: u64 = 4;
+
+fn render_line(start_radius: usize, end_radius: usize, angle: &Fraction) -> String {
+    let start = polar_to_cartesian(start_radius, angle);
+    let end = polar_to_cartesian(end_radius, angle);
+
+    format!(
+        r#"  <line x1="{:.8}" y1="{:.8}" x2="{:.8}" y2="{:.8}"/>
+"#,
+        start.x, start.y, end.x, end.y
+    )
+}
+
+fn render_arc(radius: usize, start_angle: &Fraction, end_angle: &Fraction) -> String {
+    create_svg_arc_path(radius, start_angle, end_angle, 1, 0)
+}
+
+// The passage over the crossing runs along the ring: its walls are drawn inset from the inner and
+// outer edge, and the walls of the tunnel stop where they meet them.
+fn render_radial_tunnel(inner: usize, outer: usize, start: &Fraction, end: &Fraction) -> String {
+    let mut content = String::new();
+    content.push_str(&render_arc(inner + CROSSING_INSET, start, end));
+    content.push_str(&render_arc(outer - CROSSING_INSET, start, end));
+    for angle in [start, end] {
+        content.push_str(&render_line(inner, inner + CROSSING_INSET, angle));
+        content.push_str(&render_line(outer - CROSSING_INSET, outer, angle));
+    }
+    content
+}
+
+// The passage over the crossing runs radially: its walls are drawn inset from the sides of the
+// cell, and the walls of the tunnel stop where they meet them.
+fn render_ring_tunnel(inner: usize, outer: usize, start: &Fraction, end: &Fraction) -> String {
+    let inset = (end - start) / Fraction::from(CROSSING_ANGLE_INSET_DIVISOR);
+    let inset_start = start + inset;
+    let inset_end = end - inset;
+
+    let mut content = String::new();
+    content.push_str(&render_line(inner, outer, &inset_start));
+    content.push_str(&render_line(inner, outer, &inset_end));
+    for radius in [inner, outer] {
+        content.push_str(&render_arc(radius, start, &inset_start));
+        content.push_str(&render_arc(radius, &inset_end, end));
+    }
+    content
+}
+
+fn render_crossing(coord: &CircleCoord, axis: TunnelAxis) -> String {
+    let inner = coord.circle() * CIRCLE_RADIUS_STEP;
+    let outer = inner + CIRCLE_RADIUS_STEP;
+    let total_arcs = coord.layout().total_arcs(coord.circle());
+    let start = *coord.angle();
+    let end = start + Fraction::from(DEGREES_IN_CIRCLE as u64) / Fraction::from(total_arcs);
+
+    match axis {
+        TunnelAxis::Radial => render_radial_tunnel(inner, outer, &start, &end),
+        TunnelAxis::Ring => render_ring_tunnel(inner, outer, &start, &end),
+    }
+}
+
+pub fn render_crossings(maze: &Maze) -> String {
+    let mut content = String::new();
+    content.push_str(
+        r#"<g id="crossings" fill="none" stroke="black" stroke-width="1" stroke-linecap="round">
+"#,
+    );
+    for (coord, axis) in BTreeMap::from_iter(maze.crossings()) {
+        content.push_str(&render_crossing(coord, *axis));
+    }
+    content.push_str("</g>\n");
+    content
+}
@@ -0,0 +1,70 @@
+use crate::circle_coord::CircleCoord;
+use crate::doors::{DoorPuzzle, MAX_DOORS};
+use crate::maze::Wall;
+
+use super::borders::render_wall;
+use super::geometry::{calc_display_angle, calc_display_radius, polar_to_cartesian};
+
+const DOOR_COLORS: [&str; MAX_DOORS] = [
+    "#e6194b", "#3cb44b", "#4363d8", "#f58231", "#911eb4", "#42d4f4", "#f032e6", "#9a6324",
+];
+const KEY_BOW_RADIUS: f64 = 1.5;
+const KEY_SHAFT_LENGTH: f64 = 3.0;
+
+fn render_door(wall: &Wall, color: &str) -> String {
+    format!(
+        r#"  <g stroke="{}">
+  {}  </g>
+"#,
+        color,
+        render_wall(wall)
+    )
+}
+
+fn render_key(coord: &CircleCoord, color: &str) -> String {
+    let radius = calc_display_radius(coord.circle());
+    let angle = calc_display_angle(coord);
+    let point = polar_to_cartesian(radius, &angle);
+
+    format!(
+        r#"  <g stroke="{}">
+    <circle cx="{:.8}" cy="{:.8}" r="{}"/>
+    <line x1="{:.8}" y1="{:.8}" x2="{:.8}" y2="{:.8}"/>
+  </g>
+"#,
+        color,
+        point.x - KEY_BOW_RADIUS,
+        point.y,
+        KEY_BOW_RADIUS,
+        point.x,
+        point.y,
+        point.x + KEY_SHAFT_LENGTH,
+        point.y
+    )
+}
+
+pub fn render_doors(puzzle: &DoorPuzzle) -> String {
+    let mut content = String::new();
+    content.push_str(
+        r#"<g id="doors" fill="none" stroke-width="3" stroke-linecap="butt">
+"#,
+    );
+    for (door, color) in puzzle.doors().iter().zip(DOOR_COLORS) {
+        content.push_str(&render_door(&door.wall(), color));
+    }
+    content.push_str("</g>\n");
+    content
+}
+
+pub fn render_keys(puzzle: &DoorPuzzle) -> String {
+    let mut content = String::new();
+    content.push_str(
+        r#"<g id="keys" fill="none" stroke-width="1">
+"#,
+    );
+    for (key, color) in puzzle.keys().iter().zip(DOOR_COLORS) {
+        content.push_str(&render_key(key, color));
+    }
+    content.push_str("</g>\n");
+    content
+}
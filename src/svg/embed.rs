@@ -0,0 +1,43 @@
+use crate::circle_coord::RingLayout;
+use crate::error::MazeError;
+use crate::maze::{Maze, MazeSerializer};
+use serde_json::Value;
+
+use super::escape_xml;
+use super::import::read_border_geometry;
+
+/// The id of the `<metadata>` element that carries the saved maze in exported SVGs.
+const MAZE_DATA_ID: &str = "maze-data";
+
+// The saved maze as JSON, so that the SVG alone is enough to load the maze again.
+pub fn render_maze_data(maze: &Maze) -> String {
+    let json = MazeSerializer::serialize(maze).to_string();
+    format!("<metadata id=\"{}\">{}</metadata>\n", MAZE_DATA_ID, escape_xml(&json))
+}
+
+/// Reads a saved maze from text holding either its JSON or an SVG of the maze. SVGs exported
+/// with the maze embedded give it back whole; other SVGs are read from the walls they draw.
+pub fn read_maze_text(text: &str) -> Result<Value, MazeError> {
+    if text.trim_start().starts_with('<') {
+        read_svg_maze(text)
+    } else {
+        Ok(serde_json::from_str(text)?)
+    }
+}
+
+// SVGs carry no ring layout, and mazes from before layouts were configurable all used the default
+// one, so drawn walls are read with that.
+fn read_svg_maze(svg: &str) -> Result<Value, MazeError> {
+    let doc = roxmltree::Document::parse(svg)
+        .map_err(|e| MazeError::Svg(format!("not valid XML: {}", e)))?;
+    let data = doc
+        .descendants()
+        .find(|n| n.has_tag_name("metadata") && n.attribute("id") == Some(MAZE_DATA_ID));
+    match data {
+        Some(data) => Ok(serde_json::from_str(data.text().unwrap_or_default())?),
+        None => {
+            let maze = read_border_geometry(&doc, &RingLayout::default())?;
+            Ok(MazeSerializer::serialize(&maze))
+        }
+    }
+}
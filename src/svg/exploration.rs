@@ -0,0 +1,30 @@
+use crate::solvers::SolverEvent;
+
+use super::animation::render_appearing;
+use super::cells::render_cell;
+
+const VISITED_COLOR: &str = "lightskyblue";
+const BACKTRACKED_COLOR: &str = "lightgrey";
+const FILLED_COLOR: &str = "dimgrey";
+
+/// Every explored cell lights up in the order the solver reached it, spread evenly over
+/// `duration` seconds. Cells the solver backs out of or fills in are recoloured on top.
+pub fn render_exploration(events: &[SolverEvent], duration: f64) -> String {
+    let step = duration / events.len().max(1) as f64;
+
+    let mut content = String::new();
+    content.push_str(
+        r#"<g id="exploration" stroke="none">
+"#,
+    );
+    for (i, event) in events.iter().enumerate() {
+        let (cell, color) = match event {
+            SolverEvent::Visited(cell) => (cell, VISITED_COLOR),
+            SolverEvent::Backtracked(cell) => (cell, BACKTRACKED_COLOR),
+            SolverEvent::Filled(cell) => (cell, FILLED_COLOR),
+        };
+        content.push_str(&render_appearing(&render_cell(cell, color, 1.0), i as f64 * step));
+    }
+    content.push_str("</g>\n");
+    content
+}
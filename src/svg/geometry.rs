@@ -1,4 +1,4 @@
-use crate::circle_coord::{calc_total_arcs, CircleCoord};
+use crate::circle_coord::CircleCoord;
 use std::f64::consts::PI;
 
 pub const DEGREES_IN_CIRCLE: f64 = 360.0;
@@ -91,10 +91,40 @@ pub fn calc_display_angle(coord: &CircleCoord) -> fraction::Fraction {
     if coord.circle() == 0 {
         *coord.angle()
     } else {
-        let total_arcs = calc_total_arcs(coord.circle());
+        let total_arcs = coord.layout().total_arcs(coord.circle());
         let angle_step = fraction::Fraction::from(DEGREES_IN_CIRCLE as u64)
             / fraction::Fraction::from(total_arcs);
         let half_step = angle_step / fraction::Fraction::from(2);
         coord.angle() + half_step
     }
 }
+
+/// The area a cell covers: the disc in the centre, or an annular sector for every other ring.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CellArea {
+    Disc {
+        radius: usize,
+    },
+    Sector {
+        inner_radius: usize,
+        outer_radius: usize,
+        start_angle: fraction::Fraction,
+        end_angle: fraction::Fraction,
+    },
+}
+
+pub fn calc_cell_area(coord: &CircleCoord) -> CellArea {
+    if coord.circle() == 0 {
+        return CellArea::Disc { radius: CIRCLE_RADIUS_STEP };
+    }
+
+    let total_arcs = coord.layout().total_arcs(coord.circle());
+    let angle_step =
+        fraction::Fraction::from(DEGREES_IN_CIRCLE as u64) / fraction::Fraction::from(total_arcs);
+    CellArea::Sector {
+        inner_radius: coord.circle() * CIRCLE_RADIUS_STEP,
+        outer_radius: (coord.circle() + 1) * CIRCLE_RADIUS_STEP,
+        start_angle: *coord.angle(),
+        end_angle: coord.angle() + angle_step,
+    }
+}
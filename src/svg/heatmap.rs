@@ -0,0 +1,115 @@
+use crate::circle_coord::CircleCoord;
+use crate::error::MazeError;
+use crate::maze::Maze;
+use std::collections::BTreeMap;
+
+use super::cells::render_cell_fills;
+
+pub const LEGEND_HEIGHT: usize = 12;
+const LEGEND_BAR_HEIGHT: f64 = 3.0;
+const LEGEND_FONT_SIZE: f64 = 4.0;
+
+/// Colour stops a heatmap is shaded along, from the nearest cell to the farthest.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Palette {
+    stops: Vec<[u8; 3]>,
+}
+
+impl Palette {
+    pub fn new(stops: Vec<[u8; 3]>) -> Result<Self, MazeError> {
+        if stops.len() < 2 {
+            return Err(MazeError::options("a palette needs at least 2 colours"));
+        }
+        Ok(Palette { stops })
+    }
+
+    /// One of the built-in palettes: `viridis`, `heat` or `grey`.
+    pub fn from_name(name: &str) -> Result<Self, MazeError> {
+        let stops = match name {
+            "viridis" => vec![
+                [68, 1, 84],
+                [59, 82, 139],
+                [33, 145, 140],
+                [94, 201, 98],
+                [253, 231, 37],
+            ],
+            "heat" => vec![[255, 255, 204], [253, 141, 60], [189, 0, 38]],
+            "grey" => vec![[240, 240, 240], [60, 60, 60]],
+            other => return Err(MazeError::options(format!("Unknown palette '{}'", other))),
+        };
+        Palette::new(stops)
+    }
+
+    pub fn stops(&self) -> &[[u8; 3]] {
+        &self.stops
+    }
+
+    /// The colour at `t` between 0 and 1, interpolated between the two nearest stops.
+    pub fn color_at(&self, t: f64) -> String {
+        let position = t.clamp(0.0, 1.0) * (self.stops.len() - 1) as f64;
+        let index = (position.floor() as usize).min(self.stops.len() - 2);
+        let local = position - index as f64;
+        let (from, to) = (self.stops[index], self.stops[index + 1]);
+
+        let channel = |i: usize| {
+            (f64::from(from[i]) + (f64::from(to[i]) - f64::from(from[i])) * local).round() as u8
+        };
+        format!("#{:02x}{:02x}{:02x}", channel(0), channel(1), channel(2))
+    }
+}
+
+// Fills every cell reachable from `start` with the colour of its distance. Unreachable cells
+// stay blank.
+pub fn render_heatmap_cells(maze: &Maze, start: &CircleCoord, palette: &Palette) -> String {
+    let distances = maze.distances(start);
+    let farthest = distances.values().copied().max().unwrap_or(0).max(1);
+
+    let fills: Vec<(CircleCoord, String)> = BTreeMap::from_iter(distances)
+        .into_iter()
+        .map(|(coord, distance)| {
+            let color = palette.color_at(distance as f64 / farthest as f64);
+            (coord, color)
+        })
+        .collect();
+    render_cell_fills("heatmap", &fills)
+}
+
+// A gradient bar labelled with the distances at both ends, centred at `y`.
+pub fn render_legend(palette: &Palette, farthest: usize, width: f64, y: f64) -> String {
+    let mut stops = String::new();
+    for (i, [r, g, b]) in palette.stops().iter().enumerate() {
+        let offset = i as f64 / (palette.stops().len() - 1) as f64;
+        stops.push_str(&format!(
+            r#"      <stop offset="{:.3}" stop-color="rgb({},{},{})"/>
+"#,
+            offset, r, g, b
+        ));
+    }
+
+    let left = -width / 2.0;
+    let label_y = y + LEGEND_BAR_HEIGHT / 2.0 + LEGEND_FONT_SIZE;
+    format!(
+        r#"<g id="legend">
+  <defs>
+    <linearGradient id="legend-gradient">
+{}    </linearGradient>
+  </defs>
+  <rect x="{:.8}" y="{:.8}" width="{:.8}" height="{}" fill="url(#legend-gradient)"/>
+  <text x="{:.8}" y="{:.8}" font-size="{}" text-anchor="start">0</text>
+  <text x="{:.8}" y="{:.8}" font-size="{}" text-anchor="end">{}</text>
+</g>
+"#,
+        stops,
+        left,
+        y - LEGEND_BAR_HEIGHT / 2.0,
+        width,
+        LEGEND_BAR_HEIGHT,
+        left,
+        label_y,
+        LEGEND_FONT_SIZE,
+        -left,
+        label_y,
+        LEGEND_FONT_SIZE,
+        farthest
+    )
+}
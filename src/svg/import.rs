@@ -0,0 +1,140 @@
+use crate::circle_coord::{CircleCoord, RingLayout};
+use crate::error::MazeError;
+use crate::maze::{Maze, MazeDeserializer, Wall};
+use crate::metadata::Metadata;
+use crate::validate::MAX_CIRCLES;
+use roxmltree::{Document, Node};
+
+use super::geometry::{CIRCLE_RADIUS_STEP, DEGREES_IN_CIRCLE};
+
+// How far, in rings or in cells, a drawn point may be from the grid. The coordinates are
+// written with eight decimals, so genuine walls are far closer than this.
+const GRID_TOLERANCE: f64 = 1e-4;
+
+/// Rebuilds a maze from the walls an SVG draws in its `<g id="borders">`, for SVGs that do not
+/// carry the saved maze. The drawing does not record the ring layout, so it is given. Only walls
+/// are recovered: tunnels, portals, costs and the like are drawn in other ways or not at all.
+pub fn read_border_geometry(doc: &Document, layout: &RingLayout) -> Result<Maze, MazeError> {
+    let borders = doc
+        .descendants()
+        .find(|n| n.has_tag_name("g") && n.attribute("id") == Some("borders"))
+        .ok_or_else(|| MazeError::Svg(String::from("it has no embedded maze and no borders")))?;
+
+    let mut walls = Vec::new();
+    for node in borders.children().filter(|n| n.is_element()) {
+        match node.tag_name().name() {
+            "circle" => {
+                let circle = circle_at(number(&node, "r")?)?;
+                walls.extend((0..layout.total_arcs(circle)).map(|arc_index| {
+                    Wall::Arc(CircleCoord::create_with_arc_index(circle, arc_index, layout))
+                }));
+            }
+            "path" => walls.extend(read_arc(&node, layout)?),
+            "line" => walls.extend(read_line(&node, layout)?),
+            other => {
+                return Err(MazeError::Svg(format!("unexpected <{}> in the borders", other)));
+            }
+        }
+    }
+
+    // The outer boundary is the outermost wall; lines end on it at the latest.
+    let circles = walls
+        .iter()
+        .map(|wall| match wall {
+            Wall::Arc(coord) => coord.circle(),
+            Wall::Line(coord) => coord.circle() + 1,
+        })
+        .max()
+        .ok_or_else(|| MazeError::Svg(String::from("the borders draw no walls")))?;
+
+    let mut maze = MazeDeserializer::from_walls(circles, layout, walls);
+    if let Some(title) = doc.root_element().children().find(|n| n.has_tag_name("title")) {
+        let title = Some(String::from(title.text().unwrap_or_default()));
+        maze.set_metadata(Metadata { title, ..Metadata::default() });
+    }
+    Ok(maze)
+}
+
+// A merged arc from `render_arcs`, drawn clockwise from its first wall to the end of its last.
+fn read_arc(node: &Node, layout: &RingLayout) -> Result<Vec<Wall>, MazeError> {
+    let d = node.attribute("d").unwrap_or_default();
+    let parts: Vec<&str> = d.split([' ', ',']).filter(|part| !part.is_empty()).collect();
+    let ["M", x1, y1, "A", radius, _, _, _, sweep, x2, y2] = parts[..] else {
+        return Err(MazeError::Svg(format!("the path \"{}\" is not a single arc", d)));
+    };
+
+    let circle = circle_at(parse(radius)?)?;
+    let (start, end) = (angle_at(parse(x1)?, parse(y1)?), angle_at(parse(x2)?, parse(y2)?));
+    let (start, end) = if sweep == "1" { (start, end) } else { (end, start) };
+    let start = arc_index_at(circle, start, layout)?;
+    let end = arc_index_at(circle, end, layout)?;
+
+    let total_arcs = layout.total_arcs(circle);
+    let count = match (end + total_arcs - start) % total_arcs {
+        0 => total_arcs,
+        count => count,
+    };
+    Ok((start..start + count)
+        .map(|arc_index| arc_index % total_arcs)
+        .map(|arc_index| Wall::Arc(CircleCoord::create_with_arc_index(circle, arc_index, layout)))
+        .collect())
+}
+
+// A merged line from `render_lines`, drawn outward along one angle across one or more rings.
+fn read_line(node: &Node, layout: &RingLayout) -> Result<Vec<Wall>, MazeError> {
+    let (x1, y1) = (number(node, "x1")?, number(node, "y1")?);
+    let (x2, y2) = (number(node, "x2")?, number(node, "y2")?);
+    let (inner, outer) = (x1.hypot(y1), x2.hypot(y2));
+    let (inner, outer, angle) = if inner <= outer {
+        (inner, outer, angle_at(x2, y2))
+    } else {
+        (outer, inner, angle_at(x1, y1))
+    };
+
+    (circle_at(inner)?..circle_at(outer)?)
+        .map(|circle| {
+            let arc_index = arc_index_at(circle, angle, layout)?;
+            Ok(Wall::Line(CircleCoord::create_with_arc_index(circle, arc_index, layout)))
+        })
+        .collect()
+}
+
+fn number(node: &Node, name: &str) -> Result<f64, MazeError> {
+    parse(node.attribute(name).unwrap_or_default())
+}
+
+fn parse(text: &str) -> Result<f64, MazeError> {
+    text.parse().map_err(|_| MazeError::Svg(format!("\"{}\" is not a number", text)))
+}
+
+// Every wall, and so every range of walls listed from a drawn element, lies on a circle this
+// returns, which keeps a hostile radius from listing an endless number of walls.
+fn circle_at(radius: f64) -> Result<usize, MazeError> {
+    let circle = radius / CIRCLE_RADIUS_STEP as f64;
+    if !(1.0..=MAX_CIRCLES as f64).contains(&circle.round()) {
+        return Err(MazeError::Svg(format!(
+            "a wall at radius {} lies outside the {} circles supported",
+            radius, MAX_CIRCLES
+        )));
+    }
+    if (circle - circle.round()).abs() > GRID_TOLERANCE {
+        return Err(MazeError::Svg(format!("a wall at radius {} lies between rings", radius)));
+    }
+    Ok(circle.round() as usize)
+}
+
+fn angle_at(x: f64, y: f64) -> f64 {
+    y.atan2(x).to_degrees().rem_euclid(DEGREES_IN_CIRCLE)
+}
+
+fn arc_index_at(circle: usize, angle: f64, layout: &RingLayout) -> Result<usize, MazeError> {
+    let total_arcs = layout.total_arcs(circle);
+    let arc_index = angle * total_arcs as f64 / DEGREES_IN_CIRCLE;
+    if (arc_index - arc_index.round()).abs() > GRID_TOLERANCE {
+        return Err(MazeError::Svg(format!(
+            "a wall at {:.3} degrees on circle {} does not fit the ring layout",
+            angle, circle
+        )));
+    }
+    Ok(arc_index.round() as usize % total_arcs)
+}
@@ -1,50 +1,305 @@
+mod animation;
 mod borders;
+mod branches;
+mod cells;
+mod costs;
+mod crossings;
+mod doors;
+mod embed;
+mod exploration;
 mod geometry;
+mod heatmap;
+mod import;
 mod markers;
+mod one_way;
+mod portals;
 mod solution_path;
+mod stairs;
 
+use crate::branches::{decision_points, find_branches};
 use crate::circle_coord::CircleCoord;
-use crate::maze::Maze;
-
-use borders::render_borders;
+use crate::doors::DoorPuzzle;
+use crate::levels::{LayeredMaze, LevelCoord};
+use crate::maze::{Maze, Wall};
+use crate::solvers::SolverRun;
+use std::collections::BTreeSet;
+
+use animation::{render_appearing, render_wall_animation};
+use borders::{render_border_elements, render_borders, render_borders_group, render_wall};
+use branches::{calc_branch_fills, render_decision_points};
+use cells::render_cell_fills;
+use costs::render_costs;
+use crossings::render_crossings;
+use doors::{render_doors, render_keys};
+use embed::render_maze_data;
+use exploration::render_exploration;
 use geometry::CIRCLE_RADIUS_STEP;
-use markers::render_path_markers;
-use solution_path::render_solution_path;
+use heatmap::{render_heatmap_cells, render_legend, LEGEND_HEIGHT};
+use markers::{render_marker, render_markers_group, render_path_markers};
+use one_way::render_one_way_arrows;
+use portals::render_portals;
+use solution_path::{render_solution_elements, render_solution_group, render_solution_path};
+use stairs::{render_stair_elements, render_stairs_group};
+
+pub use branches::BranchColoring;
+pub use embed::read_maze_text;
+pub use heatmap::Palette;
 
 const SVG_VIEWBOX_PADDING: usize = 20;
 
+fn calc_view_size(circles: usize) -> usize {
+    circles * CIRCLE_RADIUS_STEP * 2 + SVG_VIEWBOX_PADDING
+}
+
+/// Renders the maze with its solution path. The saved maze is embedded in the `<metadata>` of the
+/// SVG, so that it can be loaded again with `read_maze_text`.
 pub fn render(maze: &Maze, path: &[CircleCoord], include_path: bool) -> String {
-    let circles = maze.circles();
-    let max_radius = circles * CIRCLE_RADIUS_STEP;
-    let view_size = max_radius * 2 + SVG_VIEWBOX_PADDING;
+    render_with_fills(maze, &[], path, include_path)
+}
+
+/// Renders the maze like `render` with the listed cells filled in their colours underneath the
+/// walls, for highlights, masks and similar overlays.
+pub fn render_with_fills(
+    maze: &Maze,
+    fills: &[(CircleCoord, String)],
+    path: &[CircleCoord],
+    include_path: bool,
+) -> String {
+    let view_size = calc_view_size(maze.circles());
 
     let mut svg_content = String::new();
-    svg_content.push_str(&render_svg_header(view_size));
-    svg_content.push_str(&render_borders(maze));
+    svg_content.push_str(&render_svg_header(view_size, view_size));
+    svg_content.push_str(&render_title(maze));
+    svg_content.push_str(&render_maze_data(maze));
+    if !fills.is_empty() {
+        svg_content.push_str(&render_cell_fills("fills", fills));
+    }
+    svg_content.push_str(&render_maze_layers(maze));
     if include_path {
-        svg_content.push_str(&render_solution_path(path));
+        svg_content.push_str(&render_solution_path(path, maze.portals()));
+    }
+    svg_content.push_str(&render_path_markers(path));
+    svg_content.push_str("</svg>\n");
+    svg_content
+}
+
+/// Renders the maze with its dead-end branches coloured and, optionally, the cells of the
+/// solution path where a branch leaves it marked.
+pub fn render_branches(
+    maze: &Maze,
+    path: &[CircleCoord],
+    coloring: &BranchColoring,
+    mark_decisions: bool,
+) -> String {
+    let view_size = calc_view_size(maze.circles());
+    let branches = find_branches(maze, path);
+
+    let mut svg_content = String::new();
+    svg_content.push_str(&render_svg_header(view_size, view_size));
+    svg_content.push_str(&render_title(maze));
+    svg_content.push_str(&render_cell_fills("branches", &calc_branch_fills(&branches, coloring)));
+    svg_content.push_str(&render_maze_layers(maze));
+    svg_content.push_str(&render_solution_path(path, maze.portals()));
+    if mark_decisions {
+        svg_content.push_str(&render_decision_points(&decision_points(&branches)));
     }
     svg_content.push_str(&render_path_markers(path));
     svg_content.push_str("</svg>\n");
     svg_content
 }
 
-fn render_svg_header(view_size: usize) -> String {
+// The walls and the features of the maze itself, without any path or overlay.
+fn render_maze_layers(maze: &Maze) -> String {
+    let mut svg_content = String::new();
+    if !maze.costs().is_empty() {
+        svg_content.push_str(&render_costs(maze));
+    }
+    svg_content.push_str(&render_borders(maze));
+    if !maze.crossings().is_empty() {
+        svg_content.push_str(&render_crossings(maze));
+    }
+    if !maze.one_way().is_empty() {
+        svg_content.push_str(&render_one_way_arrows(maze));
+    }
+    if !maze.portals().is_empty() {
+        svg_content.push_str(&render_portals(maze));
+    }
+    svg_content
+}
+
+/// Renders an animation of the generator placing the walls of `maze` in `order`, starting from
+/// the outer boundary and taking `duration` seconds.
+pub fn render_generation(maze: &Maze, order: &[Wall], duration: f64) -> String {
+    let view_size = calc_view_size(maze.circles());
+
+    let boundary: String = BTreeSet::from_iter(maze.arcs())
+        .into_iter()
+        .filter(|arc| arc.circle() == maze.circles())
+        .map(|arc| render_wall(&Wall::Arc(arc.clone())))
+        .collect();
+
+    let mut svg_content = String::new();
+    svg_content.push_str(&render_svg_header(view_size, view_size));
+    svg_content.push_str(&render_borders_group(&boundary));
+    svg_content.push_str(&render_wall_animation(order, duration));
+    svg_content.push_str("</svg>\n");
+    svg_content
+}
+
+/// Renders an animation of a solver exploring the maze from `start` to `goal` over `duration`
+/// seconds. The route it found, if any, appears once the exploration is over.
+pub fn render_solver_run(
+    maze: &Maze,
+    run: &SolverRun,
+    start: &CircleCoord,
+    goal: &CircleCoord,
+    duration: f64,
+) -> String {
+    let view_size = calc_view_size(maze.circles());
+
+    let mut svg_content = String::new();
+    svg_content.push_str(&render_svg_header(view_size, view_size));
+    svg_content.push_str(&render_title(maze));
+    svg_content.push_str(&render_exploration(run.events(), duration));
+    svg_content.push_str(&render_maze_layers(maze));
+    if let Some(path) = run.path() {
+        let solution = render_solution_path(path, maze.portals());
+        svg_content.push_str(&render_appearing(&solution, duration));
+    }
+    svg_content.push_str(&render_path_markers(&[start.clone(), goal.clone()]));
+    svg_content.push_str("</svg>\n");
+    svg_content
+}
+
+/// Renders the maze with every cell coloured by its distance from `start`, optionally with a
+/// legend below it.
+pub fn render_heatmap(
+    maze: &Maze,
+    start: &CircleCoord,
+    palette: &Palette,
+    include_legend: bool,
+) -> String {
+    let view_size = calc_view_size(maze.circles());
+    let legend_space = if include_legend { 2 * LEGEND_HEIGHT } else { 0 };
+
+    let mut svg_content = String::new();
+    svg_content.push_str(&render_svg_header(view_size, view_size + legend_space));
+    svg_content.push_str(&render_title(maze));
+    svg_content.push_str(&render_heatmap_cells(maze, start, palette));
+    svg_content.push_str(&render_borders(maze));
+    svg_content.push_str(&render_markers_group(&render_marker(start)));
+    if include_legend {
+        let farthest = maze.distances(start).into_values().max().unwrap_or(0);
+        let width = (view_size - SVG_VIEWBOX_PADDING) as f64;
+        let y = (view_size + LEGEND_HEIGHT) as f64 / 2.0;
+        svg_content.push_str(&render_legend(palette, farthest, width, y));
+    }
+    svg_content.push_str("</svg>\n");
+    svg_content
+}
+
+/// Renders a maze with locked doors. Every door and its key share a colour.
+pub fn render_door_puzzle(puzzle: &DoorPuzzle, path: &[CircleCoord], include_path: bool) -> String {
+    let maze = puzzle.maze();
+    let view_size = calc_view_size(maze.circles());
+
+    let mut svg_content = String::new();
+    svg_content.push_str(&render_svg_header(view_size, view_size));
+    svg_content.push_str(&render_borders(maze));
+    if include_path {
+        svg_content.push_str(&render_solution_path(path, maze.portals()));
+    }
+    svg_content.push_str(&render_doors(puzzle));
+    svg_content.push_str(&render_keys(puzzle));
+    svg_content.push_str(&render_path_markers(&[puzzle.start().clone(), puzzle.goal().clone()]));
+    svg_content.push_str("</svg>\n");
+    svg_content
+}
+
+/// Renders the levels of a layered maze side by side, bottom level on the left. Stairs are
+/// drawn on both levels they connect with the same number.
+pub fn render_layered(layered: &LayeredMaze, path: &[LevelCoord], include_path: bool) -> String {
+    let levels = layered.levels();
+    let view_size = calc_view_size(levels[0].circles());
+
+    let mut borders = String::new();
+    let mut solution = String::new();
+    let mut stairs = String::new();
+    let mut markers = String::new();
+
+    for (level, maze) in levels.iter().enumerate() {
+        let offset = (level as f64 - (levels.len() - 1) as f64 / 2.0) * view_size as f64;
+
+        borders.push_str(&translate(offset, &render_border_elements(maze)));
+        stairs.push_str(&translate(offset, &render_stair_elements(layered.stairs(), level)));
+
+        for run in path.chunk_by(|a, b| a.level() == b.level()) {
+            if run[0].level() == level {
+                let coords: Vec<CircleCoord> = run.iter().map(|p| p.coord().clone()).collect();
+                let elements = render_solution_elements(&coords, maze.portals());
+                solution.push_str(&translate(offset, &elements));
+            }
+        }
+
+        for position in [path.first(), path.last()].into_iter().flatten() {
+            if position.level() == level {
+                markers.push_str(&translate(offset, &render_marker(position.coord())));
+            }
+        }
+    }
+
+    let mut svg_content = String::new();
+    svg_content.push_str(&render_svg_header(view_size * levels.len(), view_size));
+    svg_content.push_str(&render_borders_group(&borders));
+    if include_path {
+        svg_content.push_str(&render_solution_group(&solution));
+    }
+    svg_content.push_str(&render_stairs_group(&stairs));
+    svg_content.push_str(&render_markers_group(&markers));
+    svg_content.push_str("</svg>\n");
+    svg_content
+}
+
+fn translate(offset: f64, elements: &str) -> String {
+    format!(
+        r#"  <g transform="translate({:.8},0)">
+{}  </g>
+"#,
+        offset, elements
+    )
+}
+
+// The title of a maze becomes the title of the document, which viewers show as its name.
+fn render_title(maze: &Maze) -> String {
+    match &maze.metadata().title {
+        Some(title) => format!("<title>{}</title>\n", escape_xml(title)),
+        None => String::new(),
+    }
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+fn render_svg_header(view_width: usize, view_height: usize) -> String {
     format!(
         r#"<?xml version="1.0" encoding="UTF-8"?>
 <svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}" width="100%" height="100%" preserveAspectRatio="xMidYMid meet" shape-rendering="geometricPrecision">
 "#,
-        -(view_size as i32) / 2,
-        -(view_size as i32) / 2,
-        view_size,
-        view_size
+        -(view_width as i32) / 2,
+        -(view_height as i32) / 2,
+        view_width,
+        view_height
     )
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::circle_coord::calc_total_arcs;
+    use crate::circle_coord::RingLayout;
+    use crate::maze::factory;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
     use geometry::{DEGREES_IN_CIRCLE, DEGREES_IN_SEMICIRCLE, HALF_RADIUS_STEP};
     use markers::MARKER_RADIUS;
 
@@ -104,7 +359,7 @@ mod tests {
                     .unwrap_or(0);
 
                 let circle_number = r / CIRCLE_RADIUS_STEP;
-                total_steps += calc_total_arcs(circle_number);
+                total_steps += RingLayout::default().total_arcs(circle_number);
             } else if node.tag_name().name() == "path" {
                 let d = node.attribute("d").unwrap_or("");
                 let parts: Vec<&str> = d.split_whitespace().collect();
@@ -153,7 +408,7 @@ mod tests {
                             angle_diff = DEGREES_IN_CIRCLE - angle_diff;
                         }
 
-                        let total_arcs = calc_total_arcs(circle_number);
+                        let total_arcs = RingLayout::default().total_arcs(circle_number);
                         let step_count =
                             (angle_diff * total_arcs as f64 / DEGREES_IN_CIRCLE).round() as usize;
                         total_steps += step_count;
@@ -165,20 +420,25 @@ mod tests {
         total_steps
     }
 
-    fn generate_test_path(circles: usize) -> Vec<CircleCoord> {
+    fn generate_test_path(circles: usize, layout: &RingLayout) -> Vec<CircleCoord> {
         let mut path = Vec::new();
-        path.push(CircleCoord::create_with_arc_index(0, 0));
+        path.push(CircleCoord::create_with_arc_index(0, 0, layout));
         for c in 1..=circles {
-            path.push(CircleCoord::create_with_arc_index(c, 0));
+            path.push(CircleCoord::create_with_arc_index(c, 0, layout));
         }
         let outer_circle = circles;
-        let total_arcs = calc_total_arcs(outer_circle);
+        let total_arcs = layout.total_arcs(outer_circle);
         for a in 1..(total_arcs / 2) {
-            path.push(CircleCoord::create_with_arc_index(outer_circle, a));
+            path.push(CircleCoord::create_with_arc_index(outer_circle, a, layout));
         }
         path
     }
 
+    // Generates a maze with the default layout and a fixed seed, so every run draws the same one.
+    fn generate_test_maze<T>(generate: impl FnOnce(&RingLayout, &mut StdRng) -> T) -> T {
+        generate(&RingLayout::default(), &mut StdRng::seed_from_u64(42))
+    }
+
     fn for_each_fixture<F>(test_fn: F)
     where
         F: Fn(&str, &crate::maze::Maze, &serde_json::Value, &str),
@@ -214,7 +474,7 @@ mod tests {
             let maze = MazeDeserializer::deserialize(json_data.clone())
                 .unwrap_or_else(|_| panic!("Failed to deserialize maze from: {}", file_name));
 
-            let path = generate_test_path(maze.circles());
+            let path = generate_test_path(maze.circles(), maze.layout());
             let svg_string = render(&maze, &path, true);
 
             test_fn(file_name, &maze, &json_data, &svg_string);
@@ -917,4 +1177,517 @@ mod tests {
             }
         });
     }
+
+    #[test]
+    fn test_render_layered_shows_matching_stairs() {
+        use crate::levels::layered_factory;
+
+        let layered = generate_test_maze(|layout, rng| layered_factory(5, layout, 3, rng)).unwrap();
+        let path = layered.solve();
+        let svg_string = render_layered(&layered, &path, true);
+
+        let doc = roxmltree::Document::parse(&svg_string).expect("Failed to parse SVG XML");
+
+        let mut ids: Vec<_> = doc.descendants().filter_map(|n| n.attribute("id")).collect();
+        ids.sort();
+        assert_eq!(
+            ids,
+            vec!["borders", "solution-path", "stairs", "start-finish-markers"]
+        );
+
+        let stairs_g = doc
+            .descendants()
+            .find(|n| n.attribute("id") == Some("stairs"))
+            .unwrap();
+        let labels: Vec<_> = stairs_g
+            .descendants()
+            .filter(|n| n.tag_name().name() == "text")
+            .filter_map(|n| n.text())
+            .collect();
+
+        assert_eq!(labels.len(), 4);
+        assert_eq!(labels.iter().filter(|l| **l == "1").count(), 2);
+        assert_eq!(labels.iter().filter(|l| **l == "2").count(), 2);
+
+        let borders_g = doc
+            .descendants()
+            .find(|n| n.attribute("id") == Some("borders"))
+            .unwrap();
+        assert_eq!(borders_g.children().filter(|n| n.is_element()).count(), 3);
+    }
+
+    #[test]
+    fn test_render_weave_draws_crossings_instead_of_tunnel_walls() {
+        use crate::weave::weave_factory;
+
+        let maze = generate_test_maze(|layout, rng| weave_factory(10, layout, 8, rng));
+        let path = maze.tree_diameter();
+        let svg_string = render(&maze, &path, true);
+
+        let doc = roxmltree::Document::parse(&svg_string).expect("Failed to parse SVG XML");
+        let crossings_g = doc
+            .descendants()
+            .find(|n| n.attribute("id") == Some("crossings"))
+            .expect("Failed to find g element with id='crossings'");
+
+        let elements = crossings_g.children().filter(|n| n.is_element()).count();
+        assert_eq!(elements, maze.crossings().len() * 6);
+
+        let hidden = maze.tunnel_arcs().len() + maze.tunnel_lines().len();
+        let drawn =
+            count_steps_in_border_arcs(&svg_string) + count_steps_in_border_lines(&svg_string);
+        assert_eq!(drawn + hidden, maze.arcs().len() + maze.lines().len());
+    }
+
+    #[test]
+    fn test_render_one_way_draws_an_arrow_per_passage() {
+        use crate::one_way::one_way_factory;
+
+        let maze = generate_test_maze(|layout, rng| one_way_factory(8, layout, 12, rng));
+        let path = maze.tree_diameter();
+        let svg_string = render(&maze, &path, true);
+
+        let doc = roxmltree::Document::parse(&svg_string).expect("Failed to parse SVG XML");
+        let arrows = doc
+            .descendants()
+            .find(|n| n.attribute("id") == Some("one-way"))
+            .expect("Failed to find g element with id='one-way'")
+            .children()
+            .filter(|n| n.tag_name().name() == "path")
+            .count();
+        assert_eq!(arrows, maze.one_way().len());
+    }
+
+    #[test]
+    fn test_render_portals_marks_pairs_alike() {
+        use crate::portals::portal_factory;
+
+        let maze = generate_test_maze(|layout, rng| portal_factory(8, layout, 3, rng)).unwrap();
+        let path = maze.tree_diameter();
+        let svg_string = render(&maze, &path, true);
+
+        let doc = roxmltree::Document::parse(&svg_string).expect("Failed to parse SVG XML");
+        let labels: Vec<(String, String)> = doc
+            .descendants()
+            .filter(|n| n.attribute("class") == Some("portal"))
+            .map(|portal| {
+                let mut elements = portal.children().filter(|n| n.is_element());
+                let fill = elements.next().unwrap().attribute("fill").unwrap().to_string();
+                let number = elements.next().unwrap().text().unwrap().to_string();
+                (fill, number)
+            })
+            .collect();
+
+        assert_eq!(labels.len(), 6);
+        for pair in labels.chunks(2) {
+            assert_eq!(pair[0], pair[1]);
+        }
+    }
+
+    #[test]
+    fn test_render_splits_the_solution_path_at_portal_jumps() {
+
+        let layout = RingLayout::default();
+        let mut maze = generate_test_maze(|layout, rng| factory(8, layout, rng));
+        let cell = |circle, arc| CircleCoord::create_with_arc_index(circle, arc, &layout);
+        maze.add_portal(cell(3, 1), cell(6, 20));
+
+        let path = [cell(3, 0), cell(3, 1), cell(6, 20), cell(6, 21), cell(7, 21)];
+        let svg_string = render(&maze, &path, true);
+        let (endpoints, edges) = extract_solution_path_edges(&svg_string, "portal jump");
+
+        let mut degree = vec![0usize; endpoints.len()];
+        for &(a, b) in &edges {
+            degree[a] += 1;
+            degree[b] += 1;
+        }
+        let ends = degree.iter().filter(|&&d| d == 1).count();
+        assert_eq!(ends, 4, "Expected two separate stretches of path");
+    }
+
+    #[test]
+    fn test_render_keeps_the_solution_path_whole_through_tunnels() {
+        use crate::weave::weave_factory;
+
+        // The first maze whose longest path passes under a crossing.
+        let (maze, path) = (0..20)
+            .map(|seed| {
+                let mut rng = StdRng::seed_from_u64(seed);
+                let maze = weave_factory(8, &RingLayout::default(), 4, &mut rng);
+                let path = maze.tree_diameter();
+                (maze, path)
+            })
+            .find(|(_, path)| {
+                path.windows(2).any(|step| Wall::between(&step[0], &step[1]).is_none())
+            })
+            .expect("Expected a path through a tunnel");
+
+        let svg_string = render(&maze, &path, true);
+        let (endpoints, edges) = extract_solution_path_edges(&svg_string, "tunnel");
+        let mut degree = vec![0usize; endpoints.len()];
+        for &(a, b) in &edges {
+            degree[a] += 1;
+            degree[b] += 1;
+        }
+        assert_eq!(degree.iter().filter(|&&d| d == 1).count(), 2);
+    }
+
+    #[test]
+    fn test_render_portals_numbers_pairs_beyond_the_palette() {
+
+        let layout = RingLayout::default();
+        let mut maze = generate_test_maze(|layout, rng| factory(8, layout, rng));
+        for arc_index in 0..10 {
+            let a = CircleCoord::create_with_arc_index(7, 2 * arc_index, &layout);
+            let b = CircleCoord::create_with_arc_index(7, 2 * arc_index + 1, &layout);
+            maze.add_portal(a, b);
+        }
+
+        let svg_string = render_portals(&maze);
+        assert_eq!(svg_string.matches(r#"class="portal""#).count(), 20);
+        assert!(svg_string.contains(">10</text>"));
+    }
+
+    #[test]
+    fn test_render_costs_shades_expensive_cells() {
+        use crate::terrain::{cheapest_route, terrain_factory};
+
+        let maze = generate_test_maze(|layout, rng| terrain_factory(8, layout, 4, rng));
+        let path = cheapest_route(&maze);
+        let svg_string = render(&maze, &path, true);
+
+        let doc = roxmltree::Document::parse(&svg_string).expect("Failed to parse SVG XML");
+        let opacities: Vec<f64> = doc
+            .descendants()
+            .find(|n| n.attribute("id") == Some("costs"))
+            .expect("Failed to find g element with id='costs'")
+            .children()
+            .filter_map(|n| n.attribute("fill-opacity"))
+            .map(|opacity| opacity.parse().unwrap())
+            .collect();
+
+        assert_eq!(opacities.len(), maze.costs().len());
+        assert!(opacities.iter().all(|opacity| *opacity > 0.0 && *opacity <= 0.6));
+    }
+
+    #[test]
+    fn test_calc_cell_area() {
+        use geometry::{calc_cell_area, CellArea};
+        use fraction::Fraction;
+
+        let layout = RingLayout::default();
+        let centre = CircleCoord::create_with_arc_index(0, 0, &layout);
+        assert_eq!(calc_cell_area(&centre), CellArea::Disc { radius: CIRCLE_RADIUS_STEP });
+
+        let cell = CircleCoord::create_with_arc_index(2, 3, &layout);
+        assert_eq!(
+            calc_cell_area(&cell),
+            CellArea::Sector {
+                inner_radius: 2 * CIRCLE_RADIUS_STEP,
+                outer_radius: 3 * CIRCLE_RADIUS_STEP,
+                start_angle: Fraction::from(90),
+                end_angle: Fraction::from(120),
+            }
+        );
+    }
+
+    #[test]
+    fn test_render_with_fills_draws_cells_below_borders() {
+        let layout = RingLayout::default();
+        let json_str = include_str!("../../tests/fixtures/maze_04_circles_00.json");
+        let json_data = serde_json::from_str(json_str).unwrap();
+        let maze = crate::maze::MazeDeserializer::deserialize(json_data).unwrap();
+        let fills = vec![
+            (CircleCoord::create_with_arc_index(0, 0, &layout), String::from("gold")),
+            (CircleCoord::create_with_arc_index(3, 7, &layout), String::from("skyblue")),
+        ];
+        let svg_string = render_with_fills(&maze, &fills, &[], false);
+
+        let doc = roxmltree::Document::parse(&svg_string).expect("Failed to parse SVG XML");
+        let groups: Vec<&str> = doc
+            .root_element()
+            .children()
+            .filter(|n| n.has_tag_name("g"))
+            .filter_map(|n| n.attribute("id"))
+            .collect();
+        assert_eq!(&groups[..2], ["fills", "borders"]);
+
+        let fill_colors: Vec<&str> = doc
+            .descendants()
+            .find(|n| n.attribute("id") == Some("fills"))
+            .unwrap()
+            .children()
+            .filter_map(|n| n.attribute("fill"))
+            .collect();
+        assert_eq!(fill_colors, ["gold", "skyblue"]);
+    }
+
+    #[test]
+    fn test_render_branches_colours_every_cell_off_the_path() {
+
+        let layout = RingLayout::default();
+        let maze = generate_test_maze(|layout, rng| factory(6, layout, rng));
+        let path = maze.tree_diameter();
+        let coloring = BranchColoring::from_name("branch").unwrap();
+        let svg_string = render_branches(&maze, &path, &coloring, true);
+
+        let doc = roxmltree::Document::parse(&svg_string).expect("Failed to parse SVG XML");
+        let count_children = |id: &str| {
+            doc.descendants()
+                .find(|n| n.attribute("id") == Some(id))
+                .unwrap_or_else(|| panic!("Failed to find g element with id='{}'", id))
+                .children()
+                .filter(|n| n.is_element())
+                .count()
+        };
+
+        let cells: usize = (0..6).map(|c| layout.total_arcs(c)).sum();
+        assert_eq!(count_children("branches"), cells - path.len());
+
+        let branches = find_branches(&maze, &path);
+        assert_eq!(count_children("decision-points"), decision_points(&branches).len());
+        assert!(BranchColoring::from_name("random").is_err());
+    }
+
+    #[test]
+    fn test_render_generation_reveals_every_wall_in_order() {
+        use crate::maze::factory_with_history;
+
+        let (maze, order) = generate_test_maze(|layout, rng| factory_with_history(5, layout, rng));
+        let svg_string = render_generation(&maze, &order, 10.0);
+
+        let doc = roxmltree::Document::parse(&svg_string).expect("Failed to parse SVG XML");
+        let begins: Vec<f64> = doc
+            .descendants()
+            .filter(|n| n.tag_name().name() == "set")
+            .map(|n| n.attribute("begin").unwrap().trim_end_matches('s').parse().unwrap())
+            .collect();
+
+        assert_eq!(begins.len(), order.len());
+        assert_eq!(begins[0], 0.0);
+        assert!(begins.windows(2).all(|w| w[0] < w[1]));
+        assert!(*begins.last().unwrap() < 10.0);
+    }
+
+    #[test]
+    fn test_render_solver_run_shows_the_route_after_the_exploration() {
+        use crate::solvers::{run_solver, Solver};
+
+        let maze = generate_test_maze(|layout, rng| factory(5, layout, rng));
+        let route = maze.tree_diameter();
+        let (start, goal) = (route.first().unwrap(), route.last().unwrap());
+        let run = run_solver(&maze, Solver::DepthFirst, start, goal);
+        let svg_string = render_solver_run(&maze, &run, start, goal, 10.0);
+
+        let doc = roxmltree::Document::parse(&svg_string).expect("Failed to parse SVG XML");
+        let exploration = doc
+            .descendants()
+            .find(|n| n.attribute("id") == Some("exploration"))
+            .expect("Failed to find exploration group");
+        let cells = exploration.descendants().filter(|n| n.tag_name().name() == "set").count();
+        assert_eq!(cells, run.events().len());
+
+        let solution = doc
+            .descendants()
+            .find(|n| n.attribute("id") == Some("solution-path"))
+            .expect("Failed to find solution group");
+        let begin = solution
+            .ancestors()
+            .filter_map(|n| n.children().find(|c| c.tag_name().name() == "set"))
+            .find_map(|n| n.attribute("begin"))
+            .expect("Solution should appear at the end");
+        assert_eq!(begin, "10.000s");
+    }
+
+    #[test]
+    fn test_palette_interpolates_between_stops() {
+        let palette = Palette::new(vec![[0, 0, 0], [200, 100, 0], [200, 200, 200]]).unwrap();
+
+        assert_eq!(palette.color_at(0.0), "#000000");
+        assert_eq!(palette.color_at(0.25), "#643200");
+        assert_eq!(palette.color_at(0.5), "#c86400");
+        assert_eq!(palette.color_at(1.0), "#c8c8c8");
+        assert_eq!(palette.color_at(2.0), "#c8c8c8");
+
+        assert!(Palette::new(vec![[0, 0, 0]]).is_err());
+        assert!(Palette::from_name("viridis").is_ok());
+        assert!(Palette::from_name("rainbow").is_err());
+    }
+
+    #[test]
+    fn test_render_heatmap_fills_every_cell() {
+
+        let maze = generate_test_maze(|layout, rng| factory(5, layout, rng));
+        let start = CircleCoord::create_with_arc_index(0, 0, maze.layout());
+        let palette = Palette::from_name("heat").unwrap();
+        let svg_string = render_heatmap(&maze, &start, &palette, true);
+
+        let doc = roxmltree::Document::parse(&svg_string).expect("Failed to parse SVG XML");
+        let fills: Vec<&str> = doc
+            .descendants()
+            .find(|n| n.attribute("id") == Some("heatmap"))
+            .expect("Failed to find g element with id='heatmap'")
+            .children()
+            .filter_map(|n| n.attribute("fill"))
+            .collect();
+
+        let cells: usize = (0..5).map(|c| maze.layout().total_arcs(c)).sum();
+        assert_eq!(fills.len(), cells);
+        assert!(fills.contains(&palette.color_at(0.0).as_str()));
+        assert!(fills.contains(&palette.color_at(1.0).as_str()));
+
+        let legend = doc.descendants().find(|n| n.attribute("id") == Some("legend"));
+        assert!(legend.is_some());
+        let without_legend = render_heatmap(&maze, &start, &palette, false);
+        assert!(!without_legend.contains("legend"));
+    }
+
+    #[test]
+    fn test_render_door_puzzle_colours_doors_and_keys_alike() {
+        use crate::doors::door_factory;
+
+        let puzzle = generate_test_maze(|layout, rng| door_factory(8, layout, 3, rng)).unwrap();
+        let path = puzzle.solve().unwrap();
+        let svg_string = render_door_puzzle(&puzzle, &path, true);
+
+        let doc = roxmltree::Document::parse(&svg_string).expect("Failed to parse SVG XML");
+        let colours_of = |id: &str| -> Vec<String> {
+            doc.descendants()
+                .find(|n| n.attribute("id") == Some(id))
+                .unwrap()
+                .children()
+                .filter_map(|n| n.attribute("stroke"))
+                .map(String::from)
+                .collect()
+        };
+
+        let door_colours = colours_of("doors");
+        assert_eq!(door_colours.len(), puzzle.doors().len());
+        assert_eq!(door_colours, colours_of("keys"));
+    }
+
+    #[test]
+    fn test_render_is_the_same_for_equal_mazes() {
+        use crate::maze::{MazeDeserializer, MazeSerializer};
+        use crate::one_way::one_way_factory;
+        use crate::terrain::terrain_factory;
+        use crate::weave::weave_factory;
+
+        let layout = RingLayout::default();
+        let mazes = generate_test_maze(|layout, rng| {
+            [
+                weave_factory(8, layout, 6, rng),
+                one_way_factory(8, layout, 10, rng),
+                terrain_factory(8, layout, 3, rng),
+            ]
+        });
+
+        for maze in mazes {
+            // Each copy holds its walls in differently seeded hash sets.
+            let serialized = MazeSerializer::serialize(&maze);
+            let copy = MazeDeserializer::deserialize(serialized.clone()).unwrap();
+            let path = maze.tree_diameter();
+            let start = CircleCoord::create_with_arc_index(0, 0, &layout);
+            let palette = Palette::from_name("viridis").unwrap();
+
+            assert_eq!(render(&copy, &path, true), render(&maze, &path, true));
+            assert_eq!(
+                render_heatmap(&copy, &start, &palette, true),
+                render_heatmap(&maze, &start, &palette, true)
+            );
+        }
+    }
+
+    #[test]
+    fn test_render_shows_the_title() {
+        use crate::metadata::Metadata;
+
+        let mut maze = generate_test_maze(|layout, rng| factory(4, layout, rng));
+        let path = maze.tree_diameter();
+        assert!(!render(&maze, &path, true).contains("<title>"));
+
+        let title = Some(String::from("Rings & <Roses>"));
+        maze.set_metadata(Metadata { title, ..Metadata::default() });
+        let svg_string = render(&maze, &path, true);
+
+        let doc = roxmltree::Document::parse(&svg_string).expect("Failed to parse SVG XML");
+        let title = doc.descendants().find(|n| n.has_tag_name("title")).unwrap();
+        assert_eq!(title.text(), Some("Rings & <Roses>"));
+    }
+
+    #[test]
+    fn test_rendered_svg_carries_the_maze() {
+        use crate::maze::MazeSerializer;
+        use crate::metadata::Metadata;
+        use crate::weave::weave_factory;
+
+        let mut maze = generate_test_maze(|layout, rng| weave_factory(6, layout, 4, rng));
+        let title = Some(String::from("</metadata> & more"));
+        maze.set_metadata(Metadata { title, ..Metadata::default() });
+        let svg_string = render(&maze, &maze.tree_diameter(), true);
+
+        assert_eq!(read_maze_text(&svg_string), Ok(MazeSerializer::serialize(&maze)));
+    }
+
+    #[test]
+    fn test_read_maze_text_accepts_json_and_rejects_other_svgs() {
+        let json = include_str!("../../tests/fixtures/maze_03_circles_00.json");
+        assert_eq!(read_maze_text(json), Ok(serde_json::from_str(json).unwrap()));
+
+        let error = read_maze_text(&render_svg_header(10, 10)).unwrap_err();
+        assert_eq!(error.name(), "SvgError");
+        let plain = format!("{}</svg>\n", render_svg_header(10, 10));
+        assert_eq!(read_maze_text(&plain).unwrap_err().name(), "SvgError");
+    }
+
+    fn strip_maze_data(svg_string: &str) -> String {
+        let lines = svg_string.lines().filter(|line| !line.starts_with("<metadata"));
+        lines.flat_map(|line| [line, "\n"]).collect()
+    }
+
+    #[test]
+    fn test_read_maze_text_recovers_the_walls_of_plain_svgs() {
+        use crate::maze::MazeSerializer;
+        use crate::metadata::Metadata;
+
+        let mut maze = generate_test_maze(|layout, rng| factory(8, layout, rng));
+        maze.set_metadata(Metadata { title: Some(String::from("Legacy")), ..Metadata::default() });
+        let plain = strip_maze_data(&render(&maze, &maze.tree_diameter(), true));
+        assert!(!plain.contains("maze-data"));
+
+        assert_eq!(read_maze_text(&plain), Ok(MazeSerializer::serialize(&maze)));
+    }
+
+    #[test]
+    fn test_border_geometry_needs_the_ring_layout() {
+        use crate::circle_coord::Subdivision;
+
+        let mut rng = StdRng::seed_from_u64(7);
+        let layout = RingLayout::new(5, Subdivision::WidthRatio(1.5)).unwrap();
+        let maze = factory(6, &layout, &mut rng);
+        let plain = strip_maze_data(&render(&maze, &maze.tree_diameter(), true));
+        let doc = roxmltree::Document::parse(&plain).unwrap();
+
+        let recovered = import::read_border_geometry(&doc, &layout).unwrap();
+        assert_eq!(recovered.circles(), maze.circles());
+        assert_eq!(recovered.arcs(), maze.arcs());
+        assert_eq!(recovered.lines(), maze.lines());
+
+        let error = import::read_border_geometry(&doc, &RingLayout::default()).unwrap_err();
+        assert_eq!(error.name(), "SvgError");
+    }
+
+    #[test]
+    fn test_border_geometry_refuses_huge_radii() {
+        let drawings = [
+            r#"<circle cx="0" cy="0" r="1e13"/>"#,
+            r#"<circle cx="0" cy="0" r="NaN"/>"#,
+            r#"<path d="M 1e13,0 A 1e13,1e13 0 1 1 0,1e13"/>"#,
+            r#"<line x1="10" y1="0" x2="1e13" y2="0"/>"#,
+        ];
+        for drawing in drawings {
+            let svg = format!(r#"<svg><g id="borders">{}</g></svg>"#, drawing);
+            assert_eq!(read_maze_text(&svg).unwrap_err().name(), "SvgError", "{}", drawing);
+        }
+    }
 }
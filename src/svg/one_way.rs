@@ -0,0 +1,69 @@
+use crate::circle_coord::CircleCoord;
+use crate::maze::{Maze, Wall};
+use std::collections::BTreeSet;
+
+use super::geometry::{
+    calc_display_angle, calc_display_radius, polar_to_cartesian, Point, CIRCLE_RADIUS_STEP,
+};
+
+const ARROW_HALF_LENGTH: f64 = 2.5;
+const ARROW_HEAD_SIZE: f64 = 1.5;
+
+fn cell_centre(coord: &CircleCoord) -> Point {
+    polar_to_cartesian(calc_display_radius(coord.circle()), &calc_display_angle(coord))
+}
+
+// The middle of the wall the passage opens, where the arrow is drawn.
+fn opening_centre(wall: &Wall) -> Point {
+    match wall {
+        Wall::Arc(coord) => {
+            polar_to_cartesian(coord.circle() * CIRCLE_RADIUS_STEP, &calc_display_angle(coord))
+        }
+        Wall::Line(coord) => polar_to_cartesian(calc_display_radius(coord.circle()), coord.angle()),
+    }
+}
+
+fn render_arrow(from: &CircleCoord, to: &CircleCoord) -> String {
+    let Some(wall) = Wall::between(from, to) else {
+        return String::new();
+    };
+
+    let centre = opening_centre(&wall);
+    let start = cell_centre(from);
+    let end = cell_centre(to);
+    let length = (end.x - start.x).hypot(end.y - start.y);
+    let (dx, dy) = ((end.x - start.x) / length, (end.y - start.y) / length);
+
+    let tip_x = centre.x + dx * ARROW_HALF_LENGTH;
+    let tip_y = centre.y + dy * ARROW_HALF_LENGTH;
+    let base_x = tip_x - dx * ARROW_HEAD_SIZE;
+    let base_y = tip_y - dy * ARROW_HEAD_SIZE;
+
+    format!(
+        r#"  <path d="M {:.8},{:.8} L {:.8},{:.8} M {:.8},{:.8} L {:.8},{:.8} L {:.8},{:.8}"/>
+"#,
+        centre.x - dx * ARROW_HALF_LENGTH,
+        centre.y - dy * ARROW_HALF_LENGTH,
+        tip_x,
+        tip_y,
+        base_x - dy * ARROW_HEAD_SIZE,
+        base_y + dx * ARROW_HEAD_SIZE,
+        tip_x,
+        tip_y,
+        base_x + dy * ARROW_HEAD_SIZE,
+        base_y - dx * ARROW_HEAD_SIZE
+    )
+}
+
+pub fn render_one_way_arrows(maze: &Maze) -> String {
+    let mut content = String::new();
+    content.push_str(
+        r#"<g id="one-way" fill="none" stroke="darkorange" stroke-width="0.8" stroke-linecap="round" stroke-linejoin="round">
+"#,
+    );
+    for (from, to) in BTreeSet::from_iter(maze.one_way()) {
+        content.push_str(&render_arrow(from, to));
+    }
+    content.push_str("</g>\n");
+    content
+}
@@ -0,0 +1,44 @@
+use crate::circle_coord::CircleCoord;
+use crate::maze::Maze;
+use crate::portals::MAX_PORTALS;
+
+use super::geometry::{calc_display_angle, calc_display_radius, polar_to_cartesian};
+
+const PORTAL_COLORS: [&str; MAX_PORTALS] = [
+    "#1f77b4", "#ff7f0e", "#2ca02c", "#d62728", "#9467bd", "#8c564b", "#e377c2", "#17becf",
+];
+const PORTAL_RADIUS: f64 = 3.0;
+const PORTAL_FONT_SIZE: f64 = 4.0;
+
+fn render_portal(coord: &CircleCoord, number: usize, color: &str) -> String {
+    let radius = calc_display_radius(coord.circle());
+    let angle = calc_display_angle(coord);
+    let point = polar_to_cartesian(radius, &angle);
+
+    format!(
+        r#"  <g class="portal">
+    <circle cx="{:.8}" cy="{:.8}" r="{}" fill="{}"/>
+    <text x="{:.8}" y="{:.8}" font-size="{}" text-anchor="middle" dominant-baseline="central">{}</text>
+  </g>
+"#,
+        point.x, point.y, PORTAL_RADIUS, color, point.x, point.y, PORTAL_FONT_SIZE, number
+    )
+}
+
+// Both cells of a pair show the same number on the same colour. Pairs beyond the palette, which
+// only hand-written mazes have, reuse its colours; their numbers still tell them apart.
+pub fn render_portals(maze: &Maze) -> String {
+    let mut content = String::new();
+    content.push_str(
+        r#"<g id="portals" fill="white" stroke="none">
+"#,
+    );
+    let colors = PORTAL_COLORS.iter().cycle();
+    for (i, (pair, color)) in maze.portals().iter().zip(colors).enumerate() {
+        for coord in pair {
+            content.push_str(&render_portal(coord, i + 1, color));
+        }
+    }
+    content.push_str("</g>\n");
+    content
+}
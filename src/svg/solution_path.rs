@@ -41,21 +41,40 @@ impl PathSegment {
     }
 }
 
-pub fn render_solution_path(path: &[CircleCoord]) -> String {
+// A portal carries the route to a cell that is not next to the one before, so the stretches
+// between such jumps are drawn apart instead of joined through the walls.
+pub fn render_solution_elements(path: &[CircleCoord], portals: &[[CircleCoord; 2]]) -> String {
+    let is_jump = |from: &CircleCoord, to: &CircleCoord| {
+        portals.iter().any(|[a, b]| (a == from && b == to) || (a == to && b == from))
+    };
+    let mut content = String::new();
+    let mut start = 0;
+    for end in 1..=path.len() {
+        if end == path.len() || is_jump(&path[end - 1], &path[end]) {
+            for segment in merge_path_segments(&path[start..end]) {
+                content.push_str(&segment.render());
+            }
+            start = end;
+        }
+    }
+    content
+}
+
+pub fn render_solution_group(elements: &str) -> String {
     let mut content = String::new();
     content.push_str(
         r#"<g id="solution-path" fill="none" stroke="purple" stroke-width="2" stroke-linecap="round">
 "#,
     );
-
-    let segments = merge_path_segments(path);
-    for segment in segments {
-        content.push_str(&segment.render());
-    }
+    content.push_str(elements);
     content.push_str("</g>\n");
     content
 }
 
+pub fn render_solution_path(path: &[CircleCoord], portals: &[[CircleCoord; 2]]) -> String {
+    render_solution_group(&render_solution_elements(path, portals))
+}
+
 fn render_solution_arc(
     radius: usize,
     start_angle: &fraction::Fraction,
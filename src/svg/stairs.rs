@@ -0,0 +1,56 @@
+use crate::circle_coord::CircleCoord;
+
+use super::geometry::{calc_display_angle, calc_display_radius, polar_to_cartesian};
+
+pub const STAIR_HALF_SIZE: f64 = 3.0;
+const STAIR_FONT_SIZE: f64 = 5.0;
+
+fn render_stair(coord: &CircleCoord, number: usize, direction: &str) -> String {
+    let radius = calc_display_radius(coord.circle());
+    let angle = calc_display_angle(coord);
+    let point = polar_to_cartesian(radius, &angle);
+
+    format!(
+        r#"  <g class="stair-{}">
+    <rect x="{:.8}" y="{:.8}" width="{}" height="{}"/>
+    <text x="{:.8}" y="{:.8}" font-size="{}" fill="darkgreen" stroke="none" text-anchor="middle" dominant-baseline="central">{}</text>
+  </g>
+"#,
+        direction,
+        point.x - STAIR_HALF_SIZE,
+        point.y - STAIR_HALF_SIZE,
+        2.0 * STAIR_HALF_SIZE,
+        2.0 * STAIR_HALF_SIZE,
+        point.x,
+        point.y,
+        STAIR_FONT_SIZE,
+        number
+    )
+}
+
+// Stair `i` leads up from level `i` and down from level `i + 1`; both ends show the same number.
+pub fn render_stair_elements(stairs: &[CircleCoord], level: usize) -> String {
+    let mut content = String::new();
+
+    if level > 0 {
+        if let Some(stair) = stairs.get(level - 1) {
+            content.push_str(&render_stair(stair, level, "down"));
+        }
+    }
+    if let Some(stair) = stairs.get(level) {
+        content.push_str(&render_stair(stair, level + 1, "up"));
+    }
+
+    content
+}
+
+pub fn render_stairs_group(elements: &str) -> String {
+    let mut content = String::new();
+    content.push_str(
+        r#"<g id="stairs" fill="white" stroke="darkgreen" stroke-width="0.5">
+"#,
+    );
+    content.push_str(elements);
+    content.push_str("</g>\n");
+    content
+}
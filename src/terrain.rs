@@ -0,0 +1,127 @@
+use crate::circle_coord::{CircleCoord, RingLayout};
+use crate::maze::{factory, Maze, Wall};
+use rand::{seq::IndexedRandom, seq::SliceRandom, Rng};
+use std::collections::{HashSet, VecDeque};
+
+/// The cost of entering a cell of every terrain type, cheapest first. Plain ground costs 1.
+pub const TERRAIN_COSTS: [u32; 3] = [2, 4, 8];
+const PATCH_SIZE: usize = 6;
+
+/// Generates a level map with `patches` patches of rough terrain. In a perfect maze the cheapest
+/// route is the only one, so one interior wall is knocked down per patch to give the route a
+/// choice between detours and crossing the terrain.
+pub fn terrain_factory<R: Rng>(
+    circles: usize,
+    layout: &RingLayout,
+    patches: usize,
+    rng: &mut R,
+) -> Maze {
+    let mut maze = factory(circles, layout, rng);
+
+    let mut interior: Vec<Wall> = maze
+        .arcs()
+        .iter()
+        .filter(|arc| arc.circle() < circles)
+        .map(|arc| Wall::Arc(arc.clone()))
+        .chain(maze.lines().iter().map(|line| Wall::Line(line.clone())))
+        .collect();
+    // Sorted first, so that the same seed knocks down the same walls.
+    interior.sort();
+    interior.shuffle(rng);
+    for wall in interior.iter().take(patches) {
+        maze.remove_wall(wall);
+    }
+
+    let cells: Vec<CircleCoord> = (0..circles)
+        .flat_map(|c| {
+            (0..layout.total_arcs(c)).map(move |i| CircleCoord::create_with_arc_index(c, i, layout))
+        })
+        .collect();
+
+    for _ in 0..patches {
+        let Some(seed) = cells.choose(rng) else {
+            break;
+        };
+        let cost = *TERRAIN_COSTS.choose(rng).expect("There are terrain types");
+        for cell in grow_patch(&maze, seed) {
+            maze.set_cost(cell, cost);
+        }
+    }
+
+    maze
+}
+
+// The cells closest to `seed`, walking through open passages.
+fn grow_patch(maze: &Maze, seed: &CircleCoord) -> Vec<CircleCoord> {
+    let mut patch = Vec::new();
+    let mut seen = HashSet::from([seed.clone()]);
+    let mut queue = VecDeque::from([seed.clone()]);
+
+    while let Some(current) = queue.pop_front() {
+        if patch.len() == PATCH_SIZE {
+            break;
+        }
+        for neighbour in maze.accessible_neighbours(&current) {
+            if seen.insert(neighbour.clone()) {
+                queue.push_back(neighbour);
+            }
+        }
+        patch.push(current);
+    }
+
+    patch
+}
+
+/// The cheapest route between the ends of the longest path of the maze.
+pub fn cheapest_route(maze: &Maze) -> Vec<CircleCoord> {
+    let diameter = maze.tree_diameter();
+    let (start, goal) = (&diameter[0], &diameter[diameter.len() - 1]);
+    maze.cheapest_path(start, goal)
+        .map(|(_, path)| path)
+        .unwrap_or(diameter)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_terrain_factory_paints_patches() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let maze = terrain_factory(8, &RingLayout::default(), 4, &mut rng);
+
+        assert!(!maze.costs().is_empty());
+        assert!(maze.costs().len() <= 4 * PATCH_SIZE);
+        assert!(maze.costs().values().all(|cost| TERRAIN_COSTS.contains(cost)));
+    }
+
+    #[test]
+    fn test_cheapest_route_is_no_dearer_than_shortest() {
+        for seed in [42, 123, 456, 789] {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let maze = terrain_factory(8, &RingLayout::default(), 6, &mut rng);
+            let route = cheapest_route(&maze);
+            let (start, goal) = (&route[0], &route[route.len() - 1]);
+
+            let route_cost: u32 = route[1..].iter().map(|c| maze.cost(c)).sum();
+            let shortest = maze.find_path(start, goal).unwrap();
+            let shortest_cost: u32 = shortest[1..].iter().map(|c| maze.cost(c)).sum();
+
+            assert!(route_cost <= shortest_cost, "Seed {}", seed);
+            for step in route.windows(2) {
+                assert!(maze.accessible_neighbours(&step[0]).contains(&step[1]));
+            }
+        }
+    }
+
+    #[test]
+    fn test_same_seed_gives_same_terrain() {
+        let generate = || {
+            let maze = terrain_factory(8, &RingLayout::default(), 4, &mut StdRng::seed_from_u64(9));
+            (maze.lines().clone(), maze.costs().clone())
+        };
+        assert_eq!(generate(), generate());
+    }
+}
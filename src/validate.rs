@@ -0,0 +1,598 @@
+use crate::circle_coord::{CircleCoord, RingLayout};
+use crate::error::{Diagnostic, DiagnosticKind, MazeError, Severity};
+use crate::format::{CellFile, MazeFile};
+use crate::maze::{Maze, MazeDeserializer};
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// The most circles a loaded maze may have.
+pub const MAX_CIRCLES: usize = 1000;
+
+/// The most cells a loaded maze may have. Larger mazes take too long to check and to draw.
+const MAX_CELLS: usize = 100_000;
+
+/// Checks a saved maze of any format version. Files that cannot be read at all are an `Err`;
+/// everything else that is wrong with them is listed, errors before warnings.
+pub fn validate_maze(data: Value) -> Result<Vec<Diagnostic>, MazeError> {
+    let file = MazeDeserializer::read_file(data)?;
+    if file.circles == 0 {
+        let message = String::from("the maze has no circles");
+        return Ok(vec![Diagnostic::new(DiagnosticKind::Empty, None, message)]);
+    }
+    if let Ok(layout) = file.layout.to_layout() {
+        // Everything below lists every cell, so a huge maze is turned away before that.
+        if let Some(diagnostic) = check_size(file.circles, &layout) {
+            return Ok(vec![diagnostic]);
+        }
+        // A cell outside the maze has no coordinate to build the maze from.
+        let entries = check_entries(&file, &layout);
+        if entries.iter().any(|d| d.kind() == DiagnosticKind::OutOfRange) {
+            return Ok(entries);
+        }
+    }
+
+    let maze = match MazeDeserializer::from_file(&file) {
+        Ok(maze) => maze,
+        Err(MazeError::Semantic(mut diagnostics)) => {
+            if let Ok(layout) = file.layout.to_layout() {
+                diagnostics.extend(check_entries(&file, &layout));
+            }
+            return Ok(diagnostics);
+        }
+        Err(error) => return Err(error),
+    };
+    let mut diagnostics = check_entries(&file, maze.layout());
+    // Steps are only followed between cells that exist.
+    if !diagnostics.iter().any(|d| d.path().is_some_and(|p| p.starts_with("metadata.solution"))) {
+        diagnostics.extend(check_solution(&maze));
+    }
+    diagnostics.extend(check_boundary(&maze));
+    diagnostics.extend(check_connections(&maze));
+    Ok(diagnostics)
+}
+
+/// Loads a saved maze, refusing it if validation finds any errors. Warnings are ignored.
+pub fn load_valid_maze(data: Value) -> Result<Maze, MazeError> {
+    let errors: Vec<Diagnostic> = validate_maze(data.clone())?
+        .into_iter()
+        .filter(|diagnostic| diagnostic.severity() == Severity::Error)
+        .collect();
+    if !errors.is_empty() {
+        return Err(MazeError::Semantic(errors));
+    }
+    MazeDeserializer::deserialize(data)
+}
+
+/// A diagnostic as the JSON object `--validate` prints and `validate_maze_json` lists.
+pub fn serialize_diagnostic(diagnostic: &Diagnostic) -> Value {
+    let severity = match diagnostic.severity() {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+    };
+    let kind = match diagnostic.kind() {
+        DiagnosticKind::Empty => "empty",
+        DiagnosticKind::TooLarge => "too_large",
+        DiagnosticKind::InvalidValue => "invalid_value",
+        DiagnosticKind::NotAdjacent => "not_adjacent",
+        DiagnosticKind::OutOfRange => "out_of_range",
+        DiagnosticKind::Duplicate => "duplicate",
+        DiagnosticKind::ExtraWall => "extra_wall",
+        DiagnosticKind::MissingBoundary => "missing_boundary",
+        DiagnosticKind::Unreachable => "unreachable",
+        DiagnosticKind::Cycle => "cycle",
+    };
+    json!({
+        "severity": severity,
+        "kind": kind,
+        "path": diagnostic.path(),
+        "message": diagnostic.message()
+    })
+}
+
+fn check_size(circles: usize, layout: &RingLayout) -> Option<Diagnostic> {
+    let path = Some(String::from("circles"));
+    if circles > MAX_CIRCLES {
+        let message = format!("circles: {} is more than the {} supported", circles, MAX_CIRCLES);
+        return Some(Diagnostic::new(DiagnosticKind::TooLarge, path, message));
+    }
+    let mut cells: usize = 0;
+    for circle in 0..circles {
+        cells = cells.saturating_add(layout.total_arcs(circle));
+        if cells > MAX_CELLS {
+            let message = format!("circles: the maze has more than {} cells", MAX_CELLS);
+            return Some(Diagnostic::new(DiagnosticKind::TooLarge, path, message));
+        }
+    }
+    None
+}
+
+fn cell_name(circle: usize, arc: usize) -> String {
+    format!("({}, {})", circle, arc)
+}
+
+// Pairs every cell of a list with its path in the file, such as `arcs[3]`.
+fn indexed<'a>(
+    name: &str,
+    cells: impl Iterator<Item = &'a CellFile>,
+) -> Vec<(String, &'a CellFile)> {
+    cells.enumerate().map(|(i, cell)| (format!("{}[{}]", name, i), cell)).collect()
+}
+
+// Walls may lie on the outer boundary, one circle beyond the last ring of cells; everything
+// else has to be a cell.
+fn check_entries(file: &MazeFile, layout: &RingLayout) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let is_outside = |cell: &CellFile, last_circle: usize| {
+        cell.circle > last_circle || cell.arc >= layout.total_arcs(cell.circle)
+    };
+
+    // Every cell is range-checked, and the cells of a `unique` list must not repeat.
+    let check_list =
+        |cells: Vec<(String, &CellFile)>, last_circle: usize, is_wall: bool, unique: bool| {
+            let mut diagnostics = Vec::new();
+            let mut first_seen: HashMap<(usize, usize), String> = HashMap::new();
+            for (path, cell) in cells {
+                let kind = if is_outside(cell, last_circle) {
+                    let name = cell_name(cell.circle, cell.arc);
+                    let message = format!("cell {} is outside the maze", name);
+                    Some((DiagnosticKind::OutOfRange, message))
+                } else if is_wall && cell.circle == 0 {
+                    let message = String::from("the centre cell has no wall on this side");
+                    Some((DiagnosticKind::ExtraWall, message))
+                } else if !unique {
+                    None
+                } else if let Some(first) = first_seen.get(&(cell.circle, cell.arc)) {
+                    Some((DiagnosticKind::Duplicate, format!("repeats {}", first)))
+                } else {
+                    first_seen.insert((cell.circle, cell.arc), path.clone());
+                    None
+                };
+                if let Some((kind, message)) = kind {
+                    let message = format!("{}: {}", path, message);
+                    diagnostics.push(Diagnostic::new(kind, Some(path), message));
+                }
+            }
+            diagnostics
+        };
+
+    let last_ring = file.circles - 1;
+    let crossings = file.crossings.iter().map(|c| &c.cell);
+    let costs = file.costs.iter().map(|c| &c.cell);
+    diagnostics.extend(check_list(indexed("arcs", file.arcs.iter()), file.circles, true, true));
+    diagnostics.extend(check_list(indexed("lines", file.lines.iter()), last_ring, true, true));
+    diagnostics.extend(check_list(indexed("crossings", crossings), last_ring, false, true));
+    diagnostics.extend(check_list(indexed("costs", costs), last_ring, false, true));
+
+    // One-way passages may share cells, so only a passage listed twice is a duplicate.
+    let mut ends = Vec::new();
+    for (i, passage) in file.one_way.iter().enumerate() {
+        ends.push((format!("one_way[{}].from", i), &passage.from));
+        ends.push((format!("one_way[{}].to", i), &passage.to));
+    }
+    diagnostics.extend(check_list(ends, last_ring, false, false));
+    let mut first_passage: HashMap<[(usize, usize); 2], String> = HashMap::new();
+    for (i, passage) in file.one_way.iter().enumerate() {
+        let path = format!("one_way[{}]", i);
+        let key = [(passage.from.circle, passage.from.arc), (passage.to.circle, passage.to.arc)];
+        match first_passage.get(&key) {
+            Some(first) => {
+                let message = format!("{}: repeats {}", path, first);
+                diagnostics.push(Diagnostic::new(DiagnosticKind::Duplicate, Some(path), message));
+            }
+            None => {
+                first_passage.insert(key, path);
+            }
+        }
+    }
+
+    // A cell can be the end of only one portal.
+    let mut portal_cells = Vec::new();
+    for (i, [a, b]) in file.portals.iter().enumerate() {
+        portal_cells.push((format!("portals[{}][0]", i), a));
+        portal_cells.push((format!("portals[{}][1]", i), b));
+    }
+    diagnostics.extend(check_list(portal_cells, last_ring, false, true));
+
+    // A route may pass a weave crossing twice, so its cells may repeat. Its steps are checked
+    // by `check_solution` once the maze is built.
+    if let Some(metadata) = &file.metadata {
+        let mut cells: Vec<(String, &CellFile)> = Vec::new();
+        cells.extend(metadata.start.iter().map(|cell| (String::from("metadata.start"), cell)));
+        cells.extend(metadata.goal.iter().map(|cell| (String::from("metadata.goal"), cell)));
+        cells.extend(indexed("metadata.solution", metadata.solution.iter().flatten()));
+        diagnostics.extend(check_list(cells, last_ring, false, false));
+    }
+
+    diagnostics
+}
+
+// Every step of a saved solution has to follow a passage, a tunnel or a portal the way the
+// player could.
+fn check_solution(maze: &Maze) -> Vec<Diagnostic> {
+    let Some(solution) = &maze.metadata().solution else {
+        return Vec::new();
+    };
+    solution
+        .windows(2)
+        .enumerate()
+        .filter(|(_, step)| !maze.accessible_neighbours(&step[0]).contains(&step[1]))
+        .map(|(i, step)| {
+            let path = format!("metadata.solution[{}]", i + 1);
+            let (from, to) = (&step[0], &step[1]);
+            let message = format!(
+                "{}: no passage leads from {} to {}",
+                path,
+                cell_name(from.circle(), from.arc_index()),
+                cell_name(to.circle(), to.arc_index())
+            );
+            Diagnostic::new(DiagnosticKind::NotAdjacent, Some(path), message)
+        })
+        .collect()
+}
+
+fn check_boundary(maze: &Maze) -> Option<Diagnostic> {
+    let circles = maze.circles();
+    let gaps: Vec<String> = (0..maze.layout().total_arcs(circles))
+        .filter(|arc| {
+            let coord = CircleCoord::create_with_arc_index(circles, *arc, maze.layout());
+            !maze.arcs().contains(&coord)
+        })
+        .map(|arc| arc.to_string())
+        .collect();
+
+    (!gaps.is_empty()).then(|| {
+        let message = format!("the outer boundary has gaps at arcs {}", gaps.join(", "));
+        Diagnostic::new(DiagnosticKind::MissingBoundary, None, message)
+    })
+}
+
+// Walks the passages in both directions from the centre, then from every cell left over. Cells
+// outside the first walk are unreachable, and every passage that does not lead to a new cell
+// closes a loop.
+fn check_connections(maze: &Maze) -> Vec<Diagnostic> {
+    let layout = maze.layout();
+    let cells: Vec<CircleCoord> = (0..maze.circles())
+        .flat_map(|c| {
+            (0..layout.total_arcs(c))
+                .map(move |arc_index| CircleCoord::create_with_arc_index(c, arc_index, layout))
+        })
+        .collect();
+
+    let mut passages: HashMap<CircleCoord, Vec<CircleCoord>> = HashMap::new();
+    for cell in &cells {
+        for neighbour in maze.accessible_neighbours(cell) {
+            if !passages.get(cell).is_some_and(|ends| ends.contains(&neighbour)) {
+                passages.entry(cell.clone()).or_default().push(neighbour.clone());
+                passages.entry(neighbour).or_default().push(cell.clone());
+            }
+        }
+    }
+
+    let mut unreachable = Vec::new();
+    let mut loops = Vec::new();
+    let mut visited: HashSet<CircleCoord> = HashSet::new();
+    let mut tree_edges: HashSet<(CircleCoord, CircleCoord)> = HashSet::new();
+
+    for start in &cells {
+        if !visited.insert(start.clone()) {
+            continue;
+        }
+        let mut region = vec![start.clone()];
+        let mut queue = VecDeque::from([start.clone()]);
+        while let Some(current) = queue.pop_front() {
+            for neighbour in passages.get(&current).into_iter().flatten() {
+                if visited.insert(neighbour.clone()) {
+                    tree_edges.insert((current.clone(), neighbour.clone()));
+                    region.push(neighbour.clone());
+                    queue.push_back(neighbour.clone());
+                } else if is_first(&current, neighbour)
+                    && !tree_edges.contains(&(current.clone(), neighbour.clone()))
+                    && !tree_edges.contains(&(neighbour.clone(), current.clone()))
+                {
+                    loops.push((current.clone(), neighbour.clone()));
+                }
+            }
+        }
+        if start.circle() > 0 {
+            unreachable.push(region);
+        }
+    }
+
+    let mut diagnostics = Vec::new();
+    for region in unreachable {
+        let first = region
+            .iter()
+            .min_by_key(|cell| (cell.circle(), cell.arc_index()))
+            .expect("A region has at least one cell");
+        let message = format!(
+            "{} cells starting at {} cannot be reached from the centre",
+            region.len(),
+            cell_name(first.circle(), first.arc_index())
+        );
+        diagnostics.push(Diagnostic::new(DiagnosticKind::Unreachable, None, message));
+    }
+
+    loops.sort_by_key(|(a, b)| (a.circle(), a.arc_index(), b.circle(), b.arc_index()));
+    for (a, b) in loops {
+        let message = format!(
+            "the passage between {} and {} closes a loop",
+            cell_name(a.circle(), a.arc_index()),
+            cell_name(b.circle(), b.arc_index())
+        );
+        diagnostics.push(Diagnostic::new(DiagnosticKind::Cycle, None, message));
+    }
+    diagnostics
+}
+
+// Every passage is listed from both of its ends; this picks one of them.
+fn is_first(a: &CircleCoord, b: &CircleCoord) -> bool {
+    (a.circle(), a.arc_index()) < (b.circle(), b.arc_index())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::{factory, MazeSerializer};
+    use crate::terrain::terrain_factory;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use std::fs;
+
+    fn kinds(diagnostics: &[Diagnostic]) -> Vec<DiagnosticKind> {
+        diagnostics.iter().map(|d| d.kind()).collect()
+    }
+
+    #[test]
+    fn test_fixtures_and_generated_mazes_are_valid() {
+        for entry in fs::read_dir("tests/fixtures").expect("Failed to read fixtures") {
+            let path = entry.unwrap().path();
+            let data: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+            assert_eq!(validate_maze(data), Ok(vec![]), "{}", path.display());
+        }
+
+        let mut rng = StdRng::seed_from_u64(42);
+        let maze = factory(8, &RingLayout::default(), &mut rng);
+        assert_eq!(validate_maze(MazeSerializer::serialize(&maze)), Ok(vec![]));
+    }
+
+    // Every mode that saves a maze.json, saved the way the CLI saves it.
+    #[test]
+    fn test_every_generator_mode_is_valid() {
+        use crate::maze::symmetric_factory;
+        use crate::metadata::{intended_route, Metadata};
+        use crate::one_way::one_way_factory;
+        use crate::portals::portal_factory;
+        use crate::weave::weave_factory;
+
+        let layout = RingLayout::default();
+        for seed in 0..5 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let mazes = [
+                ("standard", factory(8, &layout, &mut rng)),
+                ("symmetric", symmetric_factory(8, &layout, 3, &mut rng).unwrap()),
+                ("weave", weave_factory(8, &layout, 4, &mut rng)),
+                ("one_way", one_way_factory(8, &layout, 5, &mut rng)),
+                ("portals", portal_factory(8, &layout, 3, &mut rng).unwrap()),
+                ("terrain", terrain_factory(8, &layout, 4, &mut rng)),
+            ];
+            for (algorithm, mut maze) in mazes {
+                let mut metadata = Metadata::generated(algorithm, seed, 0);
+                metadata.set_solution(&intended_route(&maze));
+                maze.set_metadata(metadata);
+
+                let errors: Vec<Diagnostic> = validate_maze(MazeSerializer::serialize(&maze))
+                    .unwrap()
+                    .into_iter()
+                    .filter(|d| d.severity() == Severity::Error)
+                    .collect();
+                assert_eq!(errors, [], "{} with seed {}", algorithm, seed);
+            }
+        }
+    }
+
+    #[test]
+    fn test_one_way_passages_may_share_cells_but_not_repeat() {
+        let mut data = small_maze(&[1, 2, 3, 4, 5], &[], &[]);
+        data["one_way"] = json!([
+            { "from": { "circle": 1, "arc": 0 }, "to": { "circle": 1, "arc": 1 } },
+            { "from": { "circle": 1, "arc": 1 }, "to": { "circle": 1, "arc": 2 } },
+            { "from": { "circle": 1, "arc": 0 }, "to": { "circle": 1, "arc": 1 } },
+            { "from": { "circle": 1, "arc": 2 }, "to": { "circle": 1, "arc": 9 } }
+        ]);
+        data["portals"] = json!([[{ "circle": 1, "arc": 3 }, { "circle": 1, "arc": 5 }]]);
+        let diagnostics = validate_maze(data).unwrap();
+
+        let located: Vec<(DiagnosticKind, Option<&str>)> =
+            diagnostics.iter().map(|d| (d.kind(), d.path())).collect();
+        assert_eq!(
+            located,
+            [
+                (DiagnosticKind::OutOfRange, Some("one_way[3].to")),
+                (DiagnosticKind::Duplicate, Some("one_way[2]")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reports_cells_outside_the_maze_and_duplicates() {
+        let data = json!({
+            "circles": 2,
+            "arcs": [
+                { "circle": 1, "arc": 0 },
+                { "circle": 1, "arc": 6 },
+                { "circle": 4, "arc": 0 },
+                { "circle": 1, "arc": 0 },
+                { "circle": 0, "arc": 0 }
+            ],
+            "lines": [{ "circle": 2, "arc": 0 }]
+        });
+        let diagnostics = validate_maze(data).unwrap();
+
+        let located: Vec<(DiagnosticKind, Option<&str>)> =
+            diagnostics.iter().take(5).map(|d| (d.kind(), d.path())).collect();
+        assert_eq!(
+            located,
+            [
+                (DiagnosticKind::OutOfRange, Some("arcs[1]")),
+                (DiagnosticKind::OutOfRange, Some("arcs[2]")),
+                (DiagnosticKind::Duplicate, Some("arcs[3]")),
+                (DiagnosticKind::ExtraWall, Some("arcs[4]")),
+                (DiagnosticKind::OutOfRange, Some("lines[0]")),
+            ]
+        );
+        assert_eq!(diagnostics[2].message(), "arcs[3]: repeats arcs[0]");
+        assert!(diagnostics.iter().all(|d| d.severity() == Severity::Error
+            || d.kind() == DiagnosticKind::Cycle));
+    }
+
+    // A maze with two rings of cells: the centre and six cells around it.
+    fn small_maze(inner_arcs: &[usize], lines: &[usize], boundary_gaps: &[usize]) -> Value {
+        let mut arcs: Vec<Value> =
+            inner_arcs.iter().map(|a| json!({ "circle": 1, "arc": a })).collect();
+        arcs.extend(
+            (0..12)
+                .filter(|a| !boundary_gaps.contains(a))
+                .map(|a| json!({ "circle": 2, "arc": a })),
+        );
+        let lines: Vec<Value> = lines.iter().map(|a| json!({ "circle": 1, "arc": a })).collect();
+        json!({ "circles": 2, "arcs": arcs, "lines": lines })
+    }
+
+    #[test]
+    fn test_reports_gaps_and_unreachable_regions() {
+        let diagnostics = validate_maze(small_maze(&[0, 1, 2, 3, 4, 5], &[0, 3], &[5])).unwrap();
+
+        let messages: Vec<&str> = diagnostics.iter().map(|d| d.message()).collect();
+        assert_eq!(
+            messages,
+            [
+                "the outer boundary has gaps at arcs 5",
+                "3 cells starting at (1, 0) cannot be reached from the centre",
+                "3 cells starting at (1, 3) cannot be reached from the centre",
+            ]
+        );
+        assert_eq!(
+            kinds(&diagnostics),
+            [
+                DiagnosticKind::MissingBoundary,
+                DiagnosticKind::Unreachable,
+                DiagnosticKind::Unreachable
+            ]
+        );
+    }
+
+    #[test]
+    fn test_loops_are_warnings() {
+        let diagnostics = validate_maze(small_maze(&[1, 2, 4, 5], &[], &[])).unwrap();
+
+        assert_eq!(kinds(&diagnostics), [DiagnosticKind::Cycle; 2]);
+        assert!(diagnostics.iter().all(|d| d.severity() == Severity::Warning));
+
+        let mut rng = StdRng::seed_from_u64(3);
+        let maze = terrain_factory(6, &RingLayout::default(), 4, &mut rng);
+        let diagnostics = validate_maze(MazeSerializer::serialize(&maze)).unwrap();
+        assert!(!diagnostics.is_empty());
+        assert!(diagnostics.iter().all(|d| d.kind() == DiagnosticKind::Cycle));
+    }
+
+    #[test]
+    fn test_reports_metadata_cells_outside_the_maze() {
+        let mut data = small_maze(&[1, 2, 3, 4, 5], &[], &[]);
+        data["metadata"] = json!({
+            "title": "Out of bounds",
+            "start": { "circle": 0, "arc": 0 },
+            "goal": { "circle": 2, "arc": 0 },
+            "solution": [{ "circle": 0, "arc": 0 }, { "circle": 1, "arc": 6 }]
+        });
+        let diagnostics = validate_maze(data).unwrap();
+
+        let paths: Vec<&str> = diagnostics.iter().filter_map(|d| d.path()).collect();
+        assert_eq!(paths, ["metadata.goal", "metadata.solution[1]"]);
+    }
+
+    #[test]
+    fn test_reports_solution_steps_through_walls() {
+        let mut data = small_maze(&[1, 2, 3, 4, 5], &[0], &[]);
+        let solution: Vec<Value> = [(0, 0), (1, 0), (1, 1), (1, 3), (1, 2), (0, 0)]
+            .iter()
+            .map(|(circle, arc)| json!({ "circle": circle, "arc": arc }))
+            .collect();
+        data["metadata"] = json!({ "solution": solution });
+        let diagnostics = validate_maze(data).unwrap();
+
+        let located: Vec<(DiagnosticKind, Option<&str>)> =
+            diagnostics.iter().map(|d| (d.kind(), d.path())).collect();
+        assert_eq!(
+            located,
+            [
+                (DiagnosticKind::NotAdjacent, Some("metadata.solution[3]")),
+                (DiagnosticKind::NotAdjacent, Some("metadata.solution[5]")),
+            ]
+        );
+        assert_eq!(
+            diagnostics[0].message(),
+            "metadata.solution[3]: no passage leads from (1, 1) to (1, 3)"
+        );
+    }
+
+    #[test]
+    fn test_cells_far_outside_the_maze_are_reported_before_it_is_built() {
+        let width_ratio =
+            json!({ "first_ring_arcs": 6, "subdivision": "width_ratio", "ratio": 1.5 });
+        let inputs = [
+            (json!({ "circle": 4611686018427387904u64, "arc": 0 }), None),
+            (json!({ "circle": 1, "arc": 18446744073709551615u64 }), None),
+            (json!({ "circle": 4000000000u64, "arc": 0 }), Some(width_ratio)),
+        ];
+        for (cell, layout) in inputs {
+            let mut data = json!({ "circles": 2, "arcs": [cell], "lines": [] });
+            if let Some(layout) = layout {
+                data["layout"] = layout;
+            }
+            let diagnostics = validate_maze(data.clone()).unwrap();
+            assert_eq!(kinds(&diagnostics), [DiagnosticKind::OutOfRange], "{}", data);
+            assert_eq!(diagnostics[0].path(), Some("arcs[0]"));
+            assert_eq!(load_valid_maze(data).unwrap_err().name(), "SemanticError");
+        }
+    }
+
+    #[test]
+    fn test_huge_mazes_are_refused_before_listing_cells() {
+        let data = json!({ "circles": 4611686018427387904u64, "arcs": [], "lines": [] });
+        assert_eq!(kinds(&validate_maze(data).unwrap()), [DiagnosticKind::TooLarge]);
+
+        let data = json!({ "circles": 900, "arcs": [], "lines": [] });
+        let diagnostics = validate_maze(data).unwrap();
+        assert_eq!(kinds(&diagnostics), [DiagnosticKind::TooLarge]);
+        assert_eq!(diagnostics[0].path(), Some("circles"));
+
+        let data = json!({
+            "circles": 2,
+            "layout": { "first_ring_arcs": 1_000_000, "subdivision": "power_of_two" },
+            "arcs": [],
+            "lines": []
+        });
+        assert_eq!(kinds(&validate_maze(data).unwrap()), [DiagnosticKind::TooLarge]);
+    }
+
+    #[test]
+    fn test_empty_maze() {
+        let data = json!({ "circles": 0, "arcs": [], "lines": [] });
+        assert_eq!(kinds(&validate_maze(data).unwrap()), [DiagnosticKind::Empty]);
+    }
+
+    #[test]
+    fn test_serialize_diagnostic() {
+        let data = json!({ "circles": 0, "arcs": [], "lines": [] });
+        let diagnostics = validate_maze(data).unwrap();
+        assert_eq!(
+            serialize_diagnostic(&diagnostics[0]),
+            json!({
+                "severity": "error",
+                "kind": "empty",
+                "path": null,
+                "message": "the maze has no circles"
+            })
+        );
+    }
+}
@@ -0,0 +1,159 @@
+use crate::circle_coord::{CircleCoord, RingLayout};
+use crate::maze::{factory, Maze, TunnelAxis, Wall};
+use rand::{seq::IndexedRandom, seq::SliceRandom, Rng};
+
+// On a ring with fewer arcs the cells on both sides of a crossing would be adjacent to each other.
+const MIN_CROSSING_RING_ARCS: usize = 4;
+
+/// Generates a weave maze: a regular maze in which up to `max_crossings` passages tunnel under a
+/// crossing corridor. Every tunnel closes one other passage, so the maze stays a spanning tree.
+pub fn weave_factory<R: Rng>(
+    circles: usize,
+    layout: &RingLayout,
+    max_crossings: usize,
+    rng: &mut R,
+) -> Maze {
+    let mut maze = factory(circles, layout, rng);
+
+    let mut candidates = Vec::new();
+    for c in 1..circles {
+        for arc_index in 0..layout.total_arcs(c) {
+            let coord = CircleCoord::create_with_arc_index(c, arc_index, layout);
+            candidates.push((coord.clone(), TunnelAxis::Radial));
+            candidates.push((coord, TunnelAxis::Ring));
+        }
+    }
+    candidates.shuffle(rng);
+
+    let mut added = 0;
+    for (coord, axis) in candidates {
+        if added == max_crossings {
+            break;
+        }
+        if try_add_crossing(&mut maze, &coord, axis, rng) {
+            added += 1;
+        }
+    }
+
+    maze
+}
+
+// Returns the cells the tunnel under `coord` connects. The passage over the crossing must run
+// straight through it, so the crossing cell needs exactly one neighbour on its outer side.
+fn tunnel_ends(maze: &Maze, coord: &CircleCoord, axis: TunnelAxis) -> Option<[CircleCoord; 2]> {
+    let layout = maze.layout();
+    let circle = coord.circle();
+
+    if circle == 0
+        || circle + 1 >= maze.circles()
+        || layout.total_arcs(circle) < MIN_CROSSING_RING_ARCS
+        || layout.total_arcs(circle) != layout.total_arcs(circle + 1)
+    {
+        return None;
+    }
+
+    match axis {
+        TunnelAxis::Radial if circle >= 2 => Some([coord.next_in(), coord.next_out()]),
+        TunnelAxis::Radial => None,
+        TunnelAxis::Ring => Some([coord.next_counter_clockwise(), coord.next_clockwise()]),
+    }
+}
+
+fn over_passage(coord: &CircleCoord, axis: TunnelAxis) -> [CircleCoord; 2] {
+    match axis {
+        TunnelAxis::Radial => [coord.next_counter_clockwise(), coord.next_clockwise()],
+        TunnelAxis::Ring => [coord.next_in(), coord.next_out()],
+    }
+}
+
+fn try_add_crossing<R: Rng>(
+    maze: &mut Maze,
+    coord: &CircleCoord,
+    axis: TunnelAxis,
+    rng: &mut R,
+) -> bool {
+    let Some([start, end]) = tunnel_ends(maze, coord, axis) else {
+        return false;
+    };
+    let over = over_passage(coord, axis);
+
+    let involved = [coord, &start, &end, &over[0], &over[1]];
+    if involved.iter().any(|c| maze.crossings().contains_key(c)) {
+        return false;
+    }
+
+    let open = maze.accessible_neighbours(coord);
+    if open.len() != 2 || !over.iter().all(|c| open.contains(c)) {
+        return false;
+    }
+
+    // The tunnel closes a cycle with the current route between its ends. Walling up a passage
+    // on that route keeps the maze a tree; passages of crossing cells must stay open.
+    let Some(route) = maze.find_path(&start, &end) else {
+        return false;
+    };
+    let walls: Vec<Wall> = route
+        .windows(2)
+        .filter(|step| {
+            step.iter()
+                .all(|c| c != coord && !maze.crossings().contains_key(c))
+        })
+        .filter_map(|step| Wall::between(&step[0], &step[1]))
+        .collect();
+
+    let Some(wall) = walls.choose(rng) else {
+        return false;
+    };
+
+    maze.add_wall(wall.clone());
+    maze.add_crossing(coord.clone(), axis);
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_weave_factory_creates_spanning_tree() {
+        let layout = RingLayout::default();
+
+        for seed in [42, 123, 456] {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let maze = weave_factory(10, &layout, 8, &mut rng);
+            assert!(!maze.crossings().is_empty(), "Seed {} added no crossings", seed);
+
+            let mut visited = HashSet::new();
+            let mut passages = 0;
+            let mut stack = vec![CircleCoord::create_with_arc_index(0, 0, &layout)];
+            while let Some(v) = stack.pop() {
+                if visited.insert(v.clone()) {
+                    let neighbours = maze.accessible_neighbours(&v);
+                    passages += neighbours.len();
+                    stack.extend(neighbours);
+                }
+            }
+
+            let cells: usize = 1 + (1..10).map(|c| layout.total_arcs(c)).sum::<usize>();
+            assert_eq!(visited.len(), cells, "Seed {} left cells unreachable", seed);
+            assert_eq!(passages / 2, cells - 1, "Seed {} does not form a tree", seed);
+        }
+    }
+
+    #[test]
+    fn test_tunnel_connects_both_ends() {
+        let layout = RingLayout::default();
+        let mut rng = StdRng::seed_from_u64(42);
+        let maze = weave_factory(10, &layout, 8, &mut rng);
+
+        for (coord, axis) in maze.crossings() {
+            let [start, end] = tunnel_ends(&maze, coord, *axis).unwrap();
+            assert!(maze.accessible_neighbours(&start).contains(&end));
+            assert!(maze.accessible_neighbours(&end).contains(&start));
+            assert!(!maze.accessible_neighbours(coord).contains(&start));
+        }
+    }
+}